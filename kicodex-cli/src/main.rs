@@ -39,6 +39,24 @@ enum Commands {
         /// Host address to bind to
         #[arg(long, default_value = "127.0.0.1")]
         host: String,
+
+        /// Always require a token, even when serving a single library, and
+        /// only accept requests for allowed hosts. Recommended with a
+        /// non-loopback --host.
+        #[arg(long)]
+        strict: bool,
+
+        /// Host name accepted in the Host header (repeatable). With --strict,
+        /// defaults to localhost and the --host address.
+        #[arg(long = "allowed-host", value_name = "HOST")]
+        allowed_hosts: Vec<String>,
+
+        /// Never write to library files: IDs missing from CSV files are
+        /// derived from the row content in memory, and duplicate IDs are
+        /// reported instead of reassigned. Projects must already be
+        /// registered with `kicodex init`.
+        #[arg(long)]
+        read_only: bool,
     },
 
     /// Initialize a KiCad project directory for use with KiCodex.
@@ -67,6 +85,10 @@ enum Commands {
         /// Name for the part table (defaults to library name for new libraries)
         #[arg(long, alias = "table", alias = "component-type")]
         part_table: Option<String>,
+
+        /// Category description for the new part table (shown in KiCad's symbol chooser)
+        #[arg(long, requires = "part_table")]
+        description: Option<String>,
     },
 
     /// Scan for libraries and generate/update kicodex.yaml
@@ -90,6 +112,10 @@ enum Commands {
         /// Output results as JSON (for CI)
         #[arg(long)]
         json: bool,
+
+        /// Never write missing or duplicate IDs back to library files
+        #[arg(long)]
+        read_only: bool,
     },
 
     /// List all registered projects and libraries
@@ -100,6 +126,67 @@ enum Commands {
         /// Path to the project directory to remove (default: current directory)
         #[arg(default_value = ".")]
         path: PathBuf,
+
+        /// Port of a running KiCodex server to unregister the project from
+        #[arg(long, default_value_t = 18734)]
+        port: u16,
+    },
+
+    /// Manage the auth tokens of registered projects
+    Token {
+        #[command(subcommand)]
+        command: TokenCommands,
+    },
+
+    /// Manage the certificate used to serve over HTTPS
+    Cert {
+        #[command(subcommand)]
+        command: CertCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenCommands {
+    /// Issue new tokens for a project (or standalone library) and rewrite
+    /// its .kicad_httplib files. The old tokens keep working during the
+    /// grace window.
+    Rotate {
+        /// Path to the project or standalone library directory (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Only rotate the token of this library (or aggregated group)
+        #[arg(long)]
+        library: Option<String>,
+
+        /// How long the old tokens keep working, e.g. `30m`, `24h`, `7d`.
+        /// Use `0` to revoke them immediately.
+        #[arg(long, default_value = "24h")]
+        grace: String,
+
+        /// Port the KiCodex server listens on (used in .kicad_httplib)
+        #[arg(long, default_value_t = 18734)]
+        port: u16,
+    },
+}
+
+#[derive(Subcommand)]
+enum CertCommands {
+    /// Generate a self-signed certificate, enable TLS in server.json and
+    /// switch .kicad_httplib files to https:// root URLs
+    Generate {
+        /// Extra host name or IP address the certificate is valid for
+        /// (repeatable). localhost, 127.0.0.1 and ::1 are always included.
+        #[arg(long = "hostname", value_name = "NAME")]
+        hostnames: Vec<String>,
+
+        /// Replace an existing certificate
+        #[arg(long)]
+        force: bool,
+
+        /// Port the KiCodex server listens on (used in .kicad_httplib)
+        #[arg(long, default_value_t = 18734)]
+        port: u16,
     },
 }
 
@@ -123,30 +210,42 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     match cli.command {
-        Commands::Serve { path, port, host } => match path {
-            Some(path) => {
-                let path = path.canonicalize().unwrap_or(path);
-                run_serve(&path, port, &host).await?;
-            }
-            None => {
-                let cwd = std::env::current_dir()?;
-                if cwd.join("kicodex.yaml").exists() {
-                    run_serve(&cwd, port, &host).await?;
-                } else {
-                    run_serve_all(port, &host).await?;
+        Commands::Serve {
+            path,
+            port,
+            host,
+            strict,
+            allowed_hosts,
+            read_only,
+        } => {
+            let policy = access_policy(&host, strict, allowed_hosts);
+            let mode = load_mode(read_only);
+            match path {
+                Some(path) => {
+                    let path = path.canonicalize().unwrap_or(path);
+                    run_serve(&path, port, &host, policy, mode).await?;
+                }
+                None => {
+                    let cwd = std::env::current_dir()?;
+                    if cwd.join("kicodex.yaml").exists() {
+                        run_serve(&cwd, port, &host, policy, mode).await?;
+                    } else {
+                        run_serve_all(port, &host, policy, mode).await?;
+                    }
                 }
             }
-        },
+        }
         Commands::Init { path, port } => {
             let path = path.canonicalize().unwrap_or(path);
-            run_init(&path, port)?;
+            run_init(&path, port).await?;
         }
         Commands::New {
             name,
             path,
             part_table,
+            description,
         } => {
-            run_new(&name, &path, part_table.as_deref())?;
+            run_new(&name, &path, part_table.as_deref(), description.as_deref())?;
         }
         Commands::Scan { path } => {
             run_scan(&path)?;
@@ -155,9 +254,10 @@ async fn main() -> anyhow::Result<()> {
             path,
             project,
             json,
+            read_only,
         } => {
             let path = path.canonicalize().unwrap_or(path);
-            let code = run_validate(&path, project.as_deref(), json)?;
+            let code = run_validate(&path, project.as_deref(), json, load_mode(read_only))?;
             if code != 0 {
                 std::process::exit(code);
             }
@@ -165,44 +265,163 @@ async fn main() -> anyhow::Result<()> {
         Commands::List => {
             run_list()?;
         }
-        Commands::Remove { path } => {
-            run_remove(&path)?;
+        Commands::Remove { path, port } => {
+            run_remove(&path, port).await?;
+        }
+        Commands::Token {
+            command:
+                TokenCommands::Rotate {
+                    path,
+                    library,
+                    grace,
+                    port,
+                },
+        } => {
+            let grace = parse_grace(&grace)?;
+            run_token_rotate(&path, library.as_deref(), grace, port).await?;
+        }
+        Commands::Cert {
+            command:
+                CertCommands::Generate {
+                    hostnames,
+                    force,
+                    port,
+                },
+        } => {
+            run_cert_generate(&hostnames, force, port)?;
         }
     }
 
     Ok(())
 }
 
+/// Library load mode selected by the `--read-only` flag.
+fn load_mode(read_only: bool) -> kicodex_core::server::LoadMode {
+    if read_only {
+        kicodex_core::server::LoadMode::ReadOnly
+    } else {
+        kicodex_core::server::LoadMode::WriteBack
+    }
+}
+
+/// Build the server's access policy from the serve flags.
+fn access_policy(
+    host: &str,
+    strict: bool,
+    mut allowed_hosts: Vec<String>,
+) -> kicodex_core::middleware::AccessPolicy {
+    let unspecified = matches!(host, "0.0.0.0" | "::" | "[::]");
+    if strict && allowed_hosts.is_empty() {
+        allowed_hosts = vec![
+            "localhost".to_string(),
+            "127.0.0.1".to_string(),
+            "::1".to_string(),
+        ];
+        if !unspecified && !allowed_hosts.iter().any(|h| h == host) {
+            allowed_hosts.push(host.to_string());
+        }
+        tracing::info!("Accepting requests for hosts: {}", allowed_hosts.join(", "));
+    }
+    let loopback = matches!(host, "127.0.0.1" | "localhost" | "::1" | "[::1]");
+    if !loopback && !strict {
+        tracing::warn!(
+            "Serving on {} without --strict: a single registered library is reachable without a token",
+            host
+        );
+    }
+    kicodex_core::middleware::AccessPolicy {
+        strict,
+        allowed_hosts,
+    }
+}
+
 /// Serve from a path: if it has kicodex.yaml, load all libraries from it;
-/// if it has library.yaml, serve that single library.
-async fn run_serve(path: &std::path::Path, port: u16, host: &str) -> anyhow::Result<()> {
-    if path.join("kicodex.yaml").exists() {
-        let config = kicodex_core::data::project::load_project_config(path)?;
+/// if it has library.yaml, serve that single library. Tokens come from the
+/// persistent registry, so `.kicad_httplib` files stay valid across restarts,
+/// and libraries are reloaded when their files change.
+async fn run_serve(
+    path: &std::path::Path,
+    port: u16,
+    host: &str,
+    policy: kicodex_core::middleware::AccessPolicy,
+    mode: kicodex_core::server::LoadMode,
+) -> anyhow::Result<()> {
+    let path = path
+        .canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf());
+    let registry_path = kicodex_core::registry::PersistedRegistry::default_path()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+    let mut persisted = kicodex_core::registry::PersistedRegistry::load(&registry_path)?;
+    let registry =
+        Arc::new(kicodex_core::registry::ProjectRegistry::new().with_load_mode(mode));
+
+    let served = if path.join("kicodex.yaml").exists() {
+        let config = kicodex_core::data::project::load_project_config(&path)?;
         if config.libraries.is_empty() {
             anyhow::bail!("kicodex.yaml has no libraries listed");
         }
 
-        let registry = kicodex_core::registry::ProjectRegistry::new();
-        for lib_ref in &config.libraries {
-            let lib_path = path.join(&lib_ref.path);
-            let lib_path = lib_path.canonicalize().unwrap_or(lib_path);
-            let library = kicodex_core::server::load_library(&lib_path)?;
-            tracing::info!(
-                "Loaded library '{}' with {} part table(s)",
-                library.name,
-                library.part_tables.len()
-            );
-            for ct in &library.part_tables {
-                tracing::info!("  {} ({} parts)", ct.name, ct.components.len());
+        // Registering writes .kicad_httplib files into the project
+        if mode == kicodex_core::server::LoadMode::WriteBack {
+            // Registers new libraries and writes or heals their .kicad_httplib
+            // files. The libraries are loaded below, broken ones as placeholders.
+            let registered = kicodex_core::discovery::auto_register::register_project(
+                &path,
+                &config,
+                &mut persisted,
+                &local_root_url(port)?,
+            )?;
+            if registered > 0 {
+                persisted.save(&registry_path)?;
+                println!(
+                    "Registered {} library/libraries of {}",
+                    registered,
+                    path.display()
+                );
             }
-            let token = uuid::Uuid::new_v4().to_string();
-            registry.insert(&token, library);
         }
 
-        let registry = Arc::new(registry);
-        kicodex_core::server::run_server_with_registry(registry, port, host).await?;
+        let path_str = path.to_string_lossy().to_string();
+        let entries: Vec<_> = persisted
+            .projects
+            .iter()
+            .filter(|p| p.project_path.as_deref() == Some(path_str.as_str()))
+            .cloned()
+            .collect();
+        if entries.is_empty() && mode == kicodex_core::server::LoadMode::ReadOnly {
+            anyhow::bail!(
+                "{} is not registered. Run `kicodex init` first to serve it with --read-only.",
+                path.display()
+            );
+        }
+        entries
     } else if path.join("library.yaml").exists() {
-        kicodex_core::server::run_server(path, port, host).await?;
+        let path_str = path.to_string_lossy().to_string();
+        let entry = match persisted
+            .projects
+            .iter()
+            .find(|p| p.project_path.is_none() && p.library_path == path_str)
+        {
+            Some(entry) => entry.clone(),
+            None => {
+                // Not registered: the token only lasts until the server stops
+                let library = kicodex_core::server::load_library_with_mode(
+                    &path,
+                    &Default::default(),
+                    mode,
+                )?;
+                kicodex_core::registry::ProjectEntry {
+                    token: uuid::Uuid::new_v4().to_string(),
+                    project_path: None,
+                    library_path: path_str,
+                    name: library.name,
+                    description: library.description,
+                    group: None,
+                    access: Default::default(),
+                }
+            }
+        };
+        vec![entry]
     } else {
         anyhow::bail!(
             "No library.yaml or kicodex.yaml found in {}. \
@@ -210,13 +429,40 @@ async fn run_serve(path: &std::path::Path, port: u16, host: &str) -> anyhow::Res
              `kicodex serve <library-dir>` to serve a single library.",
             path.display()
         );
+    };
+
+    let served = kicodex_core::registry::PersistedRegistry { projects: served };
+    registry.load_entries(&served.projects);
+    for status in registry.status() {
+        match status.error {
+            Some(error) => tracing::error!("Failed to load '{}': {}", status.name, error),
+            None => tracing::info!(
+                "Loaded library '{}' with {} part table(s), {} part(s)",
+                status.name,
+                status.part_tables,
+                status.parts
+            ),
+        }
+        if policy.strict {
+            tracing::info!("Token for '{}': {}", status.name, status.token);
+        }
     }
 
+    if let Err(e) = kicodex_core::watcher::start_watching(&served, registry.clone()) {
+        tracing::warn!("Failed to start file watcher: {}", e);
+    }
+
+    kicodex_core::server::run_server_with_policy(registry, port, host, policy).await?;
     Ok(())
 }
 
 /// Serve all registered projects from the persistent registry.
-async fn run_serve_all(port: u16, host: &str) -> anyhow::Result<()> {
+async fn run_serve_all(
+    port: u16,
+    host: &str,
+    policy: kicodex_core::middleware::AccessPolicy,
+    mode: kicodex_core::server::LoadMode,
+) -> anyhow::Result<()> {
     let registry_path = kicodex_core::registry::PersistedRegistry::default_path()
         .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
 
@@ -235,27 +481,65 @@ async fn run_serve_all(port: u16, host: &str) -> anyhow::Result<()> {
         registry_path.display()
     );
 
-    let registry = kicodex_core::registry::ProjectRegistry::from_persisted(&persisted)?;
+    let registry = kicodex_core::registry::ProjectRegistry::new().with_load_mode(mode);
+    registry.load_entries(&persisted.projects);
     let registry = Arc::new(registry);
 
-    // Start file watcher for hot-reload
-    if let Err(e) = kicodex_core::watcher::start_watching(&persisted, registry.clone()) {
+    // Start file watcher for hot-reload of libraries and of the registry itself
+    if let Err(e) = kicodex_core::watcher::start_watching_with_registry_file(
+        &registry_path,
+        &persisted,
+        registry.clone(),
+    ) {
         tracing::warn!("Failed to start file watcher: {}", e);
     }
 
-    kicodex_core::server::run_server_with_registry(registry, port, host).await?;
+    kicodex_core::server::run_server_with_policy(registry, port, host, policy).await?;
 
     Ok(())
 }
 
 /// Initialize a KiCad project: read kicodex.yaml, register each library,
 /// and write .kicad_httplib files.
-fn run_init(project_dir: &std::path::Path, port: u16) -> anyhow::Result<()> {
+async fn run_init(project_dir: &std::path::Path, port: u16) -> anyhow::Result<()> {
     let config = kicodex_core::data::project::load_project_config(project_dir)?;
 
     let registry_path = kicodex_core::registry::PersistedRegistry::default_path()
         .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
     let mut persisted = kicodex_core::registry::PersistedRegistry::load(&registry_path)?;
+    let before = persisted.clone();
+    let root_url = local_root_url(port)?;
+
+    if let Some(aggregate) = &config.aggregate {
+        // All libraries share one token and one .kicad_httplib file
+        let (_, members) = kicodex_core::discovery::auto_register::register_aggregate(
+            project_dir,
+            aggregate,
+            &config.libraries,
+            &mut persisted,
+            &root_url,
+        )?;
+        persisted.save(&registry_path)?;
+        tracing::info!("Registry saved to {}", registry_path.display());
+
+        let served = sync_project_with_server(port, project_dir, &before, &persisted).await;
+        println!(
+            "Initialized {} library/libraries as '{}'.{}",
+            members.len(),
+            aggregate.name,
+            serve_hint(served)
+        );
+        return Ok(());
+    }
+
+    // Libraries that were aggregated before get their own files again
+    let project_path_str = project_dir.to_string_lossy().to_string();
+    let stale_groups: HashSet<String> = persisted
+        .projects
+        .iter()
+        .filter(|p| p.project_path.as_deref() == Some(project_path_str.as_str()))
+        .filter_map(|p| p.group.clone())
+        .collect();
 
     for lib_ref in &config.libraries {
         let library_path = project_dir.join(&lib_ref.path);
@@ -264,7 +548,7 @@ fn run_init(project_dir: &std::path::Path, port: u16) -> anyhow::Result<()> {
             .unwrap_or_else(|_| library_path.clone());
 
         // Validate that the library can be loaded
-        let library = kicodex_core::server::load_library(&library_path)?;
+        let library = kicodex_core::server::load_library_filtered(&library_path, &lib_ref.filter)?;
         tracing::info!(
             "Validated library '{}' at {}",
             library.name,
@@ -273,8 +557,6 @@ fn run_init(project_dir: &std::path::Path, port: u16) -> anyhow::Result<()> {
 
         let token = uuid::Uuid::new_v4().to_string();
         let description = library.description.clone();
-        let fallback = format!("KiCodex HTTP Library for {}", lib_ref.name);
-        let desc_str = description.as_deref().unwrap_or(&fallback);
 
         persisted.upsert(kicodex_core::registry::ProjectEntry {
             token: token.clone(),
@@ -282,49 +564,172 @@ fn run_init(project_dir: &std::path::Path, port: u16) -> anyhow::Result<()> {
             library_path: library_path.to_string_lossy().to_string(),
             name: lib_ref.name.clone(),
             description: description.clone(),
+            group: None,
+            access: Default::default(),
         });
 
-        // Write .kicad_httplib file in the .kicodex/ subdirectory
-        let kicodex_dir = project_dir.join(".kicodex");
-        std::fs::create_dir_all(&kicodex_dir)?;
-        let httplib_path = kicodex_dir.join(format!("{}.kicad_httplib", lib_ref.name));
-        let httplib_content = format!(
-            r#"{{
-    "meta": {{
-        "version": 1.0
-    }},
-    "name": "{}",
-    "description": "{}",
-    "source": {{
-        "type": "REST_API",
-        "api_version": "v1",
-        "root_url": "http://127.0.0.1:{port}",
-        "token": "{token}"
-    }}
-}}"#,
-            lib_ref.name, desc_str
-        );
+        kicodex_core::discovery::auto_register::ensure_httplib_file(
+            project_dir,
+            &lib_ref.name,
+            description.as_deref(),
+            &token,
+            &root_url,
+        )?;
+    }
 
-        std::fs::write(&httplib_path, &httplib_content)?;
-        tracing::info!("Wrote {}", httplib_path.display());
+    for group in stale_groups {
+        kicodex_core::discovery::auto_register::remove_httplib_file(project_dir, &group)?;
     }
 
     persisted.save(&registry_path)?;
     tracing::info!("Registry saved to {}", registry_path.display());
 
+    let served = sync_project_with_server(port, project_dir, &before, &persisted).await;
     println!(
-        "Initialized {} library/libraries. Run `kicodex serve` to start the server.",
-        config.libraries.len()
+        "Initialized {} library/libraries.{}",
+        config.libraries.len(),
+        serve_hint(served)
     );
 
     Ok(())
 }
 
+fn serve_hint(served: bool) -> &'static str {
+    if served {
+        " The running server is serving them now."
+    } else {
+        " Run `kicodex serve` to start the server."
+    }
+}
+
+/// Push a project's registry changes to a running server: unregister the
+/// tokens that disappeared and (re)register the project's entries.
+/// Returns false when no server could be reached.
+async fn sync_project_with_server(
+    port: u16,
+    project_dir: &std::path::Path,
+    before: &kicodex_core::registry::PersistedRegistry,
+    after: &kicodex_core::registry::PersistedRegistry,
+) -> bool {
+    let project_path_str = project_dir.to_string_lossy().to_string();
+    let removed: Vec<String> = before
+        .projects
+        .iter()
+        .filter(|p| after.find_by_token(&p.token).is_none())
+        .map(|p| p.token.clone())
+        .collect();
+    let entries: Vec<kicodex_core::registry::ProjectEntry> = after
+        .projects
+        .iter()
+        .filter(|p| p.project_path.as_deref() == Some(project_path_str.as_str()))
+        .cloned()
+        .collect();
+    notify_running_server(port, removed, entries).await
+}
+
+/// Update a running server through its admin API, so registry changes apply
+/// without a restart. Returns false (after logging why) when there is no
+/// server to update; the registry file is then picked up on the next start.
+async fn notify_running_server(
+    port: u16,
+    unregister: Vec<String>,
+    register: Vec<kicodex_core::registry::ProjectEntry>,
+) -> bool {
+    let Some(token) = kicodex_core::admin::admin_token_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+    else {
+        tracing::debug!("No admin token found, not notifying a running server");
+        return false;
+    };
+    let (client, base_url) = match admin_client(port) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!("Not notifying a running server: {}", e);
+            return false;
+        }
+    };
+    let auth = format!("Token {token}");
+
+    if !unregister.is_empty() {
+        let result = client
+            .post(format!("{base_url}/unregister"))
+            .header("Authorization", &auth)
+            .json(&kicodex_core::models::UnregisterRequest { tokens: unregister })
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => {}
+            Ok(resp) => {
+                tracing::warn!("Server rejected unregister request: {}", resp.status());
+                return false;
+            }
+            Err(e) => {
+                tracing::debug!("No running server on port {}: {}", port, e);
+                return false;
+            }
+        }
+    }
+    if register.is_empty() {
+        return true;
+    }
+
+    let result = client
+        .post(format!("{base_url}/register"))
+        .header("Authorization", &auth)
+        .json(&kicodex_core::models::RegisterRequest { entries: register })
+        .send()
+        .await;
+    let resp = match result {
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) => {
+            tracing::warn!("Server rejected register request: {}", resp.status());
+            return false;
+        }
+        Err(e) => {
+            tracing::debug!("No running server on port {}: {}", port, e);
+            return false;
+        }
+    };
+    match resp.json::<Vec<kicodex_core::registry::LibraryStatus>>().await {
+        Ok(statuses) => {
+            for status in statuses {
+                if let Some(error) = status.error {
+                    eprintln!("Server failed to load '{}': {}", status.name, error);
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Unexpected register response: {}", e),
+    }
+    true
+}
+
+/// Root URL written into `.kicad_httplib` files for the server on `port`,
+/// following the TLS setting of `server.json`.
+fn local_root_url(port: u16) -> anyhow::Result<String> {
+    Ok(kicodex_core::config::ServerConfig::load_default()?.root_url(port))
+}
+
+/// HTTP client and base URL for the admin API of the server on `port`. With
+/// TLS configured, the server's certificate is trusted even if self-signed.
+fn admin_client(port: u16) -> anyhow::Result<(reqwest::Client, String)> {
+    let config = kicodex_core::config::ServerConfig::load_default()?;
+    let mut builder = reqwest::Client::builder();
+    if let Some(tls) = &config.tls {
+        kicodex_core::tls::install_crypto_provider();
+        let cert = reqwest::Certificate::from_pem(&std::fs::read(&tls.cert_path)?)?;
+        builder = builder.add_root_certificate(cert);
+    }
+    Ok((builder.build()?, format!("{}/internal", config.root_url(port))))
+}
+
 /// Scaffold a new library or add a part table to an existing library.
 fn run_new(
     name: &str,
     parent_dir: &std::path::Path,
     part_table: Option<&str>,
+    description: Option<&str>,
 ) -> anyhow::Result<()> {
     let lib_dir = parent_dir.join(name);
     let manifest_path = lib_dir.join("library.yaml");
@@ -338,7 +743,7 @@ fn run_new(
             )
         })?;
 
-        let mut manifest = kicodex_core::data::library::load_library_manifest(&lib_dir)?;
+        let mut manifest = kicodex_core::data::library::load_library_manifest_with_ids(&lib_dir)?;
 
         // Check for duplicate part table
         if manifest.part_tables.iter().any(|t| t.template == ct_name) {
@@ -362,12 +767,17 @@ fn run_new(
         std::fs::write(&csv_path, kicodex_core::data::schema::default_csv_headers())?;
 
         // Append part table to manifest
+        let id = manifest.next_part_table_id();
         manifest
             .part_tables
             .push(kicodex_core::data::library::PartTableDef {
+                id: Some(id),
                 name: capitalize(ct_name),
                 file: format!("{}.csv", ct_name),
                 template: ct_name.to_string(),
+                description: description.map(|d| d.to_string()),
+                notes: None,
+                id_prefix: None,
             });
 
         let yaml = serde_yml::to_string(&manifest)?;
@@ -403,9 +813,13 @@ fn run_new(
             std::fs::write(&csv_path, kicodex_core::data::schema::default_csv_headers())?;
 
             part_tables.push(kicodex_core::data::library::PartTableDef {
+                id: Some("1".to_string()),
                 name: capitalize(ct_name),
                 file: format!("{}.csv", ct_name),
                 template: ct_name.to_string(),
+                description: description.map(|d| d.to_string()),
+                notes: None,
+                id_prefix: None,
             });
 
             println!("Created library '{}' at {}/", name, lib_dir.display());
@@ -428,6 +842,8 @@ fn run_new(
             description: Some(format!("KiCodex library: {}", name)),
             templates_path: "templates".to_string(),
             part_tables,
+            views: Vec::new(),
+            ids: Default::default(),
         };
         let yaml = serde_yml::to_string(&manifest)?;
         std::fs::write(&manifest_path, yaml)?;
@@ -457,6 +873,7 @@ fn run_scan(scan_dir: &std::path::Path) -> anyhow::Result<()> {
         kicodex_core::data::project::load_project_config(scan_dir)?
     } else {
         kicodex_core::data::project::ProjectConfig {
+            aggregate: None,
             libraries: Vec::new(),
         }
     };
@@ -500,6 +917,7 @@ fn run_scan(scan_dir: &std::path::Path) -> anyhow::Result<()> {
                 .push(kicodex_core::data::project::LibraryRef {
                     name: manifest.name.clone(),
                     path: rel_path_str,
+                    filter: Default::default(),
                 });
             new_count += 1;
         }
@@ -550,6 +968,7 @@ fn run_validate(
     path: &std::path::Path,
     project: Option<&std::path::Path>,
     json_output: bool,
+    mode: kicodex_core::server::LoadMode,
 ) -> anyhow::Result<i32> {
     // Determine library paths to validate
     let library_roots: Vec<std::path::PathBuf> = if path.join("kicodex.yaml").exists() {
@@ -591,7 +1010,7 @@ fn run_validate(
 
     let mut total_exit_code = 0;
     for library_root in &library_roots {
-        let code = validate_library(library_root, kicad_libs.as_ref(), json_output)?;
+        let code = validate_library(library_root, kicad_libs.as_ref(), json_output, mode)?;
         if code != 0 {
             total_exit_code = code;
         }
@@ -637,8 +1056,13 @@ fn validate_library(
     library_root: &std::path::Path,
     kicad_libs: Option<&kicodex_core::data::kicad_libs::KicadLibraries>,
     json_output: bool,
+    mode: kicodex_core::server::LoadMode,
 ) -> anyhow::Result<i32> {
-    let library = kicodex_core::server::load_library(library_root)?;
+    use kicodex_core::data::ids::IdStrategy;
+    use kicodex_core::data::lifecycle::{self, Lifecycle, LIFECYCLE_COLUMN};
+
+    let library =
+        kicodex_core::server::load_library_with_mode(library_root, &Default::default(), mode)?;
     let manifest = kicodex_core::data::library::load_library_manifest(library_root)?;
 
     // Build a map from part table name -> csv file path
@@ -648,20 +1072,151 @@ fn validate_library(
         .map(|t| (t.name.clone(), t.file.clone()))
         .collect();
 
+    // Category IDs are cached by KiCad, so changing one remaps placed parts
+    let committed = committed_library_manifest(library_root);
+    let id_changes = committed
+        .as_ref()
+        .map(|committed| kicodex_core::data::library::part_table_id_changes(committed, &manifest))
+        .unwrap_or_default();
+    let view_id_changes = committed
+        .as_ref()
+        .map(|committed| kicodex_core::data::library::view_id_changes(committed, &manifest))
+        .unwrap_or_default();
+
     let mut issues: Vec<ValidationIssue> = Vec::new();
 
+    for change in &view_id_changes {
+        issues.push(ValidationIssue {
+            severity: Severity::Error,
+            part_table: change.name.clone(),
+            file: "library.yaml".to_string(),
+            row: None,
+            id: None,
+            message: format!(
+                "view id changed from '{}' to '{}' since HEAD (KiCad caches category ids)",
+                change.old_id, change.new_id
+            ),
+        });
+    }
+
+    // Part tables that failed to load are not served at all
+    for d in &library.diagnostics {
+        issues.push(ValidationIssue {
+            severity: Severity::Error,
+            part_table: d.part_table.clone(),
+            file: ct_files.get(&d.part_table).cloned().unwrap_or_default(),
+            row: None,
+            id: None,
+            message: d.error.clone(),
+        });
+    }
+
     for ct in &library.part_tables {
         let csv_file = ct_files
             .get(&ct.name)
             .cloned()
             .unwrap_or_else(|| ct.template_name.clone());
 
+        // Check 0: Category ID changed since the last commit
+        for change in id_changes.iter().filter(|c| c.name == ct.name) {
+            issues.push(ValidationIssue {
+                severity: Severity::Error,
+                part_table: ct.name.clone(),
+                file: csv_file.clone(),
+                row: None,
+                id: None,
+                message: format!(
+                    "category id changed from '{}' to '{}' since HEAD (KiCad caches category ids)",
+                    change.old_id, change.new_id
+                ),
+            });
+        }
+
         let csv_headers: HashSet<&String> = ct
             .components
             .first()
             .map(|r| r.keys().collect())
             .unwrap_or_default();
 
+        // Check 0a: Pattern ID prefixes are not shared with other part tables
+        if let Some(def) = manifest.part_tables.iter().find(|t| t.name == ct.name) {
+            let prefix = def.id_prefix();
+            let sharing: Vec<String> = manifest
+                .part_tables
+                .iter()
+                .filter(|t| t.name != ct.name && t.id_prefix() == prefix)
+                .map(|t| format!("'{}'", t.name))
+                .collect();
+            if manifest.ids.strategy == IdStrategy::Pattern && !sharing.is_empty() {
+                issues.push(ValidationIssue {
+                    severity: Severity::Warn,
+                    part_table: ct.name.clone(),
+                    file: csv_file.clone(),
+                    row: None,
+                    id: None,
+                    message: format!(
+                        "id_prefix '{}' is shared with {}; their IDs share one counter",
+                        prefix,
+                        sharing.join(", ")
+                    ),
+                });
+            }
+        }
+
+        // Check 0b: Name/description formats have balanced braces and their
+        // placeholders reference known fields
+        for (format_key, format) in [
+            ("name_format", ct.template.name_format.as_deref()),
+            ("description_format", ct.template.description_format.as_deref()),
+        ] {
+            if let Some(error) = format.and_then(kicodex_core::data::schema::format_brace_error) {
+                issues.push(ValidationIssue {
+                    severity: Severity::Error,
+                    part_table: ct.name.clone(),
+                    file: csv_file.clone(),
+                    row: None,
+                    id: None,
+                    message: format!("{}: {}", format_key, error),
+                });
+            }
+            for placeholder in format
+                .map(kicodex_core::data::schema::format_placeholders)
+                .unwrap_or_default()
+            {
+                let message = if placeholder != "id"
+                    && !ct.template.fields.contains_key(&placeholder)
+                    && !csv_headers.contains(&placeholder)
+                {
+                    "does not match any field"
+                } else if !ct.template.is_exported(&placeholder) {
+                    "refers to an internal field and renders as empty"
+                } else {
+                    continue;
+                };
+                issues.push(ValidationIssue {
+                    severity: Severity::Warn,
+                    part_table: ct.name.clone(),
+                    file: csv_file.clone(),
+                    row: None,
+                    id: None,
+                    message: format!("{} placeholder '{{{}}}' {}", format_key, placeholder, message),
+                });
+            }
+        }
+
+        // Check 0c: Computed fields have no cycles or unknown references
+        let columns: Vec<&String> = csv_headers.iter().copied().collect();
+        for issue in kicodex_core::data::computed::check_computed_fields(&ct.template, &columns) {
+            issues.push(ValidationIssue {
+                severity: Severity::Error,
+                part_table: ct.name.clone(),
+                file: csv_file.clone(),
+                row: None,
+                id: None,
+                message: issue.to_string(),
+            });
+        }
+
         // Check 1: Required fields present as CSV columns
         for (field_name, field_def) in &ct.template.fields {
             if field_def.required && !csv_headers.contains(field_name) {
@@ -698,6 +1253,66 @@ fn validate_library(
                 });
             }
 
+            // Check 3b: IDs are unique across part tables; KiCad can only
+            // reach the first table's part
+            if let Some((other, _)) = library.find_part(&row_id).filter(|(other, _)| other.id != ct.id) {
+                issues.push(ValidationIssue {
+                    severity: Severity::Error,
+                    part_table: ct.name.clone(),
+                    file: csv_file.clone(),
+                    row: Some(row_num),
+                    id: Some(row_id.clone()),
+                    message: format!("id '{}' is also used in part table '{}'", row_id, other.name),
+                });
+            }
+
+            // Check 7: Lifecycle status is recognized
+            if let Some(status) = row.get(LIFECYCLE_COLUMN) {
+                if Lifecycle::parse(status).is_none() {
+                    issues.push(ValidationIssue {
+                        severity: Severity::Warn,
+                        part_table: ct.name.clone(),
+                        file: csv_file.clone(),
+                        row: Some(row_num),
+                        id: Some(row_id.clone()),
+                        message: format!(
+                            "unknown lifecycle '{}' (expected active, nrnd, obsolete or draft); treated as active",
+                            status
+                        ),
+                    });
+                }
+            }
+
+            // Check 8: Replacement part ids resolve
+            if let Some(replacement) = lifecycle::replacement(row) {
+                match library.find_part(replacement) {
+                    None => issues.push(ValidationIssue {
+                        severity: Severity::Error,
+                        part_table: ct.name.clone(),
+                        file: csv_file.clone(),
+                        row: Some(row_num),
+                        id: Some(row_id.clone()),
+                        message: format!("replacement id '{}' does not exist", replacement),
+                    }),
+                    Some((_, replacement_row)) => {
+                        let status = Lifecycle::of_row(replacement_row);
+                        if !status.is_active() {
+                            issues.push(ValidationIssue {
+                                severity: Severity::Warn,
+                                part_table: ct.name.clone(),
+                                file: csv_file.clone(),
+                                row: Some(row_num),
+                                id: Some(row_id.clone()),
+                                message: format!(
+                                    "replacement id '{}' is itself {}",
+                                    replacement, status
+                                ),
+                            });
+                        }
+                    }
+                }
+            }
+
             for (field_name, field_def) in &ct.template.fields {
                 let value = row.get(field_name).map(|s| s.as_str()).unwrap_or("");
                 let field_type = field_def.field_type.as_deref();
@@ -875,10 +1490,26 @@ fn validate_library(
                 })
             })
             .collect();
+        let view_json: Vec<serde_json::Value> = library
+            .views
+            .iter()
+            .map(|view| {
+                let errors: Vec<_> = issues
+                    .iter()
+                    .filter(|i| i.part_table == view.name && i.severity == Severity::Error)
+                    .map(issue_to_json)
+                    .collect();
+                json!({
+                    "name": view.name,
+                    "errors": errors,
+                })
+            })
+            .collect();
 
         let output = json!({
             "library": library.name,
             "part_tables": ct_json,
+            "views": view_json,
             "error_count": error_count,
             "warning_count": warning_count,
         });
@@ -897,9 +1528,14 @@ fn validate_library(
             for issue in &issues {
                 if issue.part_table != current_part_table {
                     current_part_table = issue.part_table.clone();
+                    let kind = if library.views.iter().any(|v| v.name == issue.part_table) {
+                        "View"
+                    } else {
+                        "Part table"
+                    };
                     println!(
-                        "Part table '{}' ({}):",
-                        current_part_table, issue.file
+                        "{} '{}' ({}):",
+                        kind, current_part_table, issue.file
                     );
                 }
 
@@ -936,6 +1572,22 @@ fn validate_library(
     Ok(if error_count > 0 { 1 } else { 0 })
 }
 
+/// Read library.yaml as committed at `HEAD`, if the library lives in a git checkout.
+fn committed_library_manifest(
+    library_root: &std::path::Path,
+) -> Option<kicodex_core::data::library::LibraryManifest> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(library_root)
+        .args(["show", "HEAD:./library.yaml"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    serde_yml::from_str(&String::from_utf8_lossy(&output.stdout)).ok()
+}
+
 fn issue_to_json(issue: &ValidationIssue) -> serde_json::Value {
     let mut obj = serde_json::Map::new();
     if let Some(row) = issue.row {
@@ -980,17 +1632,13 @@ fn run_list() -> anyhow::Result<()> {
             } else {
                 entry.token.clone()
             };
-            let ct_count = match kicodex_core::server::load_library(std::path::Path::new(
-                &entry.library_path,
-            )) {
-                Ok(lib) => format!("{} part table(s)", lib.part_tables.len()),
-                Err(_) => "unable to load".to_string(),
-            };
+            let (ct_count, diagnostics) = list_summary(&entry.library_path);
             println!(
                 "  {} [{}] (token: {})",
                 entry.name, ct_count, short_token
             );
             println!("    {}", entry.library_path);
+            print_load_diagnostics("    ", &diagnostics);
         }
         println!();
     }
@@ -1006,16 +1654,12 @@ fn run_list() -> anyhow::Result<()> {
                 } else {
                     entry.token.clone()
                 };
-                let ct_count = match kicodex_core::server::load_library(std::path::Path::new(
-                    &entry.library_path,
-                )) {
-                    Ok(lib) => format!("{} part table(s)", lib.part_tables.len()),
-                    Err(_) => "unable to load".to_string(),
-                };
+                let (ct_count, diagnostics) = list_summary(&entry.library_path);
                 println!(
                     "    {} [{}] (token: {})",
                     entry.name, ct_count, short_token
                 );
+                print_load_diagnostics("      ", &diagnostics);
             }
             println!();
         }
@@ -1024,9 +1668,27 @@ fn run_list() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Part table count of a registered library, and the part tables that
+/// failed to load.
+fn list_summary(library_path: &str) -> (String, Vec<kicodex_core::server::LoadDiagnostic>) {
+    match kicodex_core::server::load_library(std::path::Path::new(library_path)) {
+        Ok(lib) => (
+            format!("{} part table(s)", lib.part_tables.len()),
+            lib.diagnostics,
+        ),
+        Err(_) => ("unable to load".to_string(), Vec::new()),
+    }
+}
+
+fn print_load_diagnostics(indent: &str, diagnostics: &[kicodex_core::server::LoadDiagnostic]) {
+    for d in diagnostics {
+        println!("{}! part table '{}' not loaded: {}", indent, d.part_table, d.error);
+    }
+}
+
 /// Remove a project or standalone library from the registry.
 /// If the path contains library.yaml (not kicodex.yaml), treats it as standalone library removal.
-fn run_remove(path: &std::path::Path) -> anyhow::Result<()> {
+async fn run_remove(path: &std::path::Path, port: u16) -> anyhow::Result<()> {
     let path = path
         .canonicalize()
         .unwrap_or_else(|_| path.to_path_buf());
@@ -1056,6 +1718,8 @@ fn run_remove(path: &std::path::Path) -> anyhow::Result<()> {
 
         persisted.remove_by_library_path(&path_str);
         persisted.save(&registry_path)?;
+        let tokens = standalone_match.iter().map(|p| p.token.clone()).collect();
+        notify_running_server(port, tokens, Vec::new()).await;
 
         println!(
             "Removed standalone library at {}",
@@ -1087,6 +1751,8 @@ fn run_remove(path: &std::path::Path) -> anyhow::Result<()> {
         // Remove from registry
         persisted.remove_by_path(&path_str);
         persisted.save(&registry_path)?;
+        let tokens = matching.iter().map(|p| p.token.clone()).collect();
+        notify_running_server(port, tokens, Vec::new()).await;
 
         println!(
             "Removed {} library/libraries for project at {}",
@@ -1098,6 +1764,145 @@ fn run_remove(path: &std::path::Path) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Parse a grace window like `90s`, `30m`, `24h` or `7d` (`0` is none).
+fn parse_grace(value: &str) -> anyhow::Result<std::time::Duration> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid grace window '{}'", value))?;
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => anyhow::bail!("invalid grace window unit '{}', expected s, m, h or d", unit),
+    };
+    let seconds = number
+        .checked_mul(seconds)
+        .ok_or_else(|| anyhow::anyhow!("grace window '{}' is too long", value))?;
+    Ok(std::time::Duration::from_secs(seconds))
+}
+
+/// Rotate the tokens of a project (or standalone library) and push the new
+/// tokens to a running server.
+async fn run_token_rotate(
+    path: &std::path::Path,
+    library: Option<&str>,
+    grace: std::time::Duration,
+    port: u16,
+) -> anyhow::Result<()> {
+    let path = path
+        .canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf());
+    let path_str = path.to_string_lossy().to_string();
+
+    let registry_path = kicodex_core::registry::PersistedRegistry::default_path()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+    let mut persisted = kicodex_core::registry::PersistedRegistry::load(&registry_path)?;
+
+    let mut tokens: Vec<(String, String)> = Vec::new();
+    for entry in &persisted.projects {
+        let matches_path = match entry.project_path.as_deref() {
+            Some(project_path) => project_path == path_str,
+            None => entry.library_path == path_str,
+        };
+        let matches_library =
+            library.is_none_or(|l| entry.name == l || entry.httplib_name() == l);
+        if matches_path && matches_library && !tokens.iter().any(|(t, _)| *t == entry.token) {
+            tokens.push((entry.token.clone(), entry.httplib_name().to_string()));
+        }
+    }
+    if tokens.is_empty() {
+        anyhow::bail!("No registered library found at {}", path.display());
+    }
+
+    let root_url = local_root_url(port)?;
+    let mut old_tokens = Vec::new();
+    let mut new_tokens = Vec::new();
+    for (token, name) in tokens {
+        let new_token = kicodex_core::discovery::auto_register::rotate_token(
+            &mut persisted,
+            &token,
+            grace,
+            &root_url,
+        )?;
+        println!("Rotated token for '{}'", name);
+        old_tokens.push(token);
+        new_tokens.push(new_token);
+    }
+    persisted.save(&registry_path)?;
+    tracing::info!("Registry saved to {}", registry_path.display());
+
+    if grace.is_zero() {
+        println!("The old token(s) are revoked.");
+    } else if let Some(previous) = persisted
+        .find_by_token(&new_tokens[0])
+        .and_then(|e| e.access.previous_token.as_ref())
+    {
+        println!(
+            "The old token(s) keep working until {}.",
+            previous.valid_until.format("%Y-%m-%d %H:%M UTC")
+        );
+    }
+
+    let entries = persisted
+        .projects
+        .iter()
+        .filter(|e| new_tokens.contains(&e.token))
+        .cloned()
+        .collect();
+    if !notify_running_server(port, old_tokens, entries).await {
+        println!("No running server was updated; the new tokens apply on its next start.");
+    }
+
+    Ok(())
+}
+
+/// Generate a self-signed certificate next to the registry and serve over
+/// HTTPS from now on.
+fn run_cert_generate(hostnames: &[String], force: bool, port: u16) -> anyhow::Result<()> {
+    let config_path = kicodex_core::config::ServerConfig::default_path()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+    let tls_dir = config_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?
+        .join("tls");
+    let tls = kicodex_core::config::TlsConfig {
+        cert_path: tls_dir.join("cert.pem"),
+        key_path: tls_dir.join("key.pem"),
+    };
+    if tls.cert_path.exists() && !force {
+        anyhow::bail!(
+            "A certificate already exists at {}. Use --force to replace it.",
+            tls.cert_path.display()
+        );
+    }
+
+    let cert = kicodex_core::tls::generate_self_signed(hostnames)?;
+    cert.write(&tls)?;
+    println!("Wrote certificate to {}", tls.cert_path.display());
+    println!("Wrote private key to {}", tls.key_path.display());
+
+    let mut config = kicodex_core::config::ServerConfig::load(&config_path)?;
+    config.tls = Some(tls.clone());
+    config.save(&config_path)?;
+    tracing::info!("Server config saved to {}", config_path.display());
+
+    // Point KiCad at the https:// root URL
+    if let Some(registry_path) = kicodex_core::registry::PersistedRegistry::default_path() {
+        let persisted = kicodex_core::registry::PersistedRegistry::load(&registry_path)?;
+        kicodex_core::discovery::auto_register::ensure_all_httplib_files(&persisted, &config.root_url(port));
+    }
+
+    println!(
+        "TLS enabled. Restart the KiCodex server to serve over HTTPS, and add {} \
+         to the trusted certificates of the machine running KiCad.",
+        tls.cert_path.display()
+    );
+    Ok(())
+}
+
 /// Register a library as standalone in the persisted registry.
 fn register_standalone_library(lib_dir: &std::path::Path, name: &str) -> anyhow::Result<()> {
     let registry_path = kicodex_core::registry::PersistedRegistry::default_path()
@@ -1125,6 +1930,8 @@ fn register_standalone_library(lib_dir: &std::path::Path, name: &str) -> anyhow:
         library_path: lib_path_str,
         name: name.to_string(),
         description: Some(format!("KiCodex library: {}", name)),
+        group: None,
+        access: Default::default(),
     });
 
     persisted.save(&registry_path)?;
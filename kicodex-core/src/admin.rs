@@ -0,0 +1,156 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::rejection::ExtensionRejection;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Router;
+use tower_http::trace::TraceLayer;
+
+use crate::registry::{PersistedRegistry, ProjectRegistry};
+use crate::routes;
+
+/// File holding the admin API token, next to `projects.json`.
+pub const ADMIN_TOKEN_FILE: &str = "admin_token";
+
+/// Get the default admin token file path.
+pub fn admin_token_path() -> Option<PathBuf> {
+    let registry_path = PersistedRegistry::default_path()?;
+    Some(registry_path.parent()?.join(ADMIN_TOKEN_FILE))
+}
+
+/// Read the admin token, generating and saving a new one if the file is
+/// missing or empty. The file is only readable by the current user.
+pub fn load_or_create_admin_token(path: &Path) -> std::io::Result<String> {
+    if let Ok(token) = std::fs::read_to_string(path) {
+        let token = token.trim();
+        if !token.is_empty() {
+            return Ok(token.to_string());
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let token = uuid::Uuid::new_v4().to_string();
+    std::fs::write(path, &token)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(token)
+}
+
+/// Shared state of the admin routes.
+#[derive(Clone)]
+pub struct AdminState {
+    pub registry: Arc<ProjectRegistry>,
+    pub token: Arc<str>,
+}
+
+/// Middleware requiring `Authorization: Token <admin token>`. Unlike the
+/// library routes, the check is never skipped.
+///
+/// The admin API shares the library API's listener, which may be exposed to
+/// the network with `--host`, so requests from anywhere but this machine are
+/// rejected with 403. The server must be run with
+/// `into_make_service_with_connect_info::<SocketAddr>()` for the peer
+/// address to be known; without it every request is rejected.
+async fn admin_auth_middleware(
+    State(state): State<AdminState>,
+    peer: Result<ConnectInfo<SocketAddr>, ExtensionRejection>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let from_loopback = peer.is_ok_and(|ConnectInfo(peer)| peer.ip().to_canonical().is_loopback());
+    if !from_loopback {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let token = req
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Token "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if !tokens_match(token, &state.token) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(next.run(req).await)
+}
+
+/// Compare tokens in time independent of where they first differ.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a
+            .bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// Build the admin router (`/internal/...`), used by `kicodex init` and
+/// `kicodex remove` to update a running server.
+pub fn admin_router(registry: Arc<ProjectRegistry>, token: &str) -> Router {
+    let state = AdminState {
+        registry,
+        token: Arc::from(token),
+    };
+    Router::new()
+        .route(
+            "/internal/status",
+            axum::routing::get(routes::admin::get_status),
+        )
+        .route(
+            "/internal/projects",
+            axum::routing::get(routes::admin::get_projects),
+        )
+        .route(
+            "/internal/register",
+            axum::routing::post(routes::admin::post_register),
+        )
+        .route(
+            "/internal/unregister",
+            axum::routing::post(routes::admin::post_unregister),
+        )
+        .route(
+            "/internal/reload",
+            axum::routing::post(routes::admin::post_reload),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            admin_auth_middleware,
+        ))
+        .layer(TraceLayer::new_for_http())
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_admin_token_is_created_once() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("kicodex").join(ADMIN_TOKEN_FILE);
+
+        let token = load_or_create_admin_token(&path).unwrap();
+        assert!(!token.is_empty());
+        assert_eq!(load_or_create_admin_token(&path).unwrap(), token);
+
+        std::fs::write(&path, "  custom-token\n").unwrap();
+        assert_eq!(load_or_create_admin_token(&path).unwrap(), "custom-token");
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("admin-secret", "admin-secret"));
+        assert!(!tokens_match("admin-secret", "admin-secreT"));
+        assert!(!tokens_match("admin", "admin-secret"));
+        assert!(!tokens_match("", "admin-secret"));
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::registry::PersistedRegistry;
+
+/// File holding the server settings, next to `projects.json`.
+pub const SERVER_CONFIG_FILE: &str = "server.json";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read/write server config: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse server config JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Server settings shared by the CLI and the tray app.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Serve over HTTPS with this certificate. Plain HTTP when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+    /// Serve Prometheus metrics at `/metrics`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub metrics: bool,
+}
+
+/// PEM certificate chain and private key used to serve HTTPS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl ServerConfig {
+    /// Get the default server config path.
+    pub fn default_path() -> Option<PathBuf> {
+        let registry_path = PersistedRegistry::default_path()?;
+        Some(registry_path.parent()?.join(SERVER_CONFIG_FILE))
+    }
+
+    /// Load the config from disk. Returns the default config if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Load the config from the default path, if there is one.
+    pub fn load_default() -> Result<Self, ConfigError> {
+        match Self::default_path() {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Save the config to disk, creating parent directories as needed.
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Root URL clients use to reach a server on `port` of this machine.
+    pub fn root_url(&self, port: u16) -> String {
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        format!("{scheme}://127.0.0.1:{port}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_url_follows_tls_setting() {
+        let mut config = ServerConfig::default();
+        assert_eq!(config.root_url(18734), "http://127.0.0.1:18734");
+
+        config.tls = Some(TlsConfig {
+            cert_path: PathBuf::from("cert.pem"),
+            key_path: PathBuf::from("key.pem"),
+        });
+        assert_eq!(config.root_url(18734), "https://127.0.0.1:18734");
+    }
+
+    #[test]
+    fn test_load_save_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SERVER_CONFIG_FILE);
+        assert_eq!(ServerConfig::load(&path).unwrap(), ServerConfig::default());
+
+        let config = ServerConfig {
+            tls: Some(TlsConfig {
+                cert_path: dir.path().join("cert.pem"),
+                key_path: dir.path().join("key.pem"),
+            }),
+            metrics: true,
+        };
+        config.save(&path).unwrap();
+        assert_eq!(ServerConfig::load(&path).unwrap(), config);
+    }
+}
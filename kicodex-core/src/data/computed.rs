@@ -0,0 +1,225 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::data::csv_loader::CsvRow;
+use crate::data::schema::{self, ResolvedSchema};
+
+/// A problem with a template's computed (`expr`) fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComputedIssue {
+    /// These computed fields reference each other in a cycle (or depend on one).
+    Cycle(Vec<String>),
+    /// A computed field references a field that is neither in the template nor the CSV.
+    UnknownReference { field: String, reference: String },
+}
+
+impl fmt::Display for ComputedIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComputedIssue::Cycle(fields) => {
+                write!(f, "computed fields form a cycle: {}", fields.join(", "))
+            }
+            ComputedIssue::UnknownReference { field, reference } => write!(
+                f,
+                "computed field '{}' references unknown field '{}'",
+                field, reference
+            ),
+        }
+    }
+}
+
+/// Order the template's computed fields so each one is evaluated after the
+/// computed fields it references. Fields caught in a cycle are left out of the
+/// order and returned as a `ComputedIssue::Cycle`.
+pub fn evaluation_order(schema: &ResolvedSchema) -> (Vec<String>, Option<ComputedIssue>) {
+    let computed: HashMap<&str, Vec<String>> = schema
+        .fields
+        .iter()
+        .filter_map(|(name, def)| {
+            let expr = def.expr.as_deref()?;
+            Some((name.as_str(), schema::format_placeholders(expr)))
+        })
+        .collect();
+
+    // Kahn's algorithm over computed → computed dependencies, in template order
+    let mut order = Vec::new();
+    let mut done: HashSet<&str> = HashSet::new();
+    loop {
+        let ready: Vec<&str> = schema
+            .fields
+            .keys()
+            .map(|k| k.as_str())
+            .filter(|name| computed.contains_key(name) && !done.contains(name))
+            .filter(|name| {
+                computed[name]
+                    .iter()
+                    .all(|dep| !computed.contains_key(dep.as_str()) || done.contains(dep.as_str()))
+            })
+            .collect();
+        if ready.is_empty() {
+            break;
+        }
+        for name in ready {
+            done.insert(name);
+            order.push(name.to_string());
+        }
+    }
+
+    let stuck: Vec<String> = schema
+        .fields
+        .keys()
+        .filter(|k| computed.contains_key(k.as_str()) && !done.contains(k.as_str()))
+        .cloned()
+        .collect();
+    let cycle = (!stuck.is_empty()).then_some(ComputedIssue::Cycle(stuck));
+
+    (order, cycle)
+}
+
+/// Check computed fields for cycles and references to fields that are neither
+/// in the template nor among `columns`.
+pub fn check_computed_fields(schema: &ResolvedSchema, columns: &[&String]) -> Vec<ComputedIssue> {
+    let mut issues = Vec::new();
+    for (name, def) in &schema.fields {
+        let Some(expr) = def.expr.as_deref() else {
+            continue;
+        };
+        for reference in schema::format_placeholders(expr) {
+            if reference != "id"
+                && !schema.fields.contains_key(&reference)
+                && !columns.iter().any(|c| **c == reference)
+            {
+                issues.push(ComputedIssue::UnknownReference {
+                    field: name.clone(),
+                    reference,
+                });
+            }
+        }
+    }
+    if let (_, Some(cycle)) = evaluation_order(schema) {
+        issues.push(cycle);
+    }
+    issues
+}
+
+/// Evaluate the template's computed fields for every row, adding (or replacing)
+/// them as columns. Fields in a cycle are skipped with a warning.
+pub fn apply_computed_fields(schema: &ResolvedSchema, rows: &mut [CsvRow]) {
+    let (order, cycle) = evaluation_order(schema);
+    if let Some(cycle) = cycle {
+        tracing::warn!("{}, skipping them", cycle);
+    }
+    if order.is_empty() {
+        return;
+    }
+
+    for row in rows {
+        for name in &order {
+            let expr = schema.fields[name].expr.as_deref().unwrap_or_default();
+            let value = schema::render_format(expr, row);
+            row.insert(name.clone(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::schema::FieldDef;
+    use indexmap::IndexMap;
+
+    fn schema_with(fields: &[(&str, Option<&str>)]) -> ResolvedSchema {
+        let fields: IndexMap<String, FieldDef> = fields
+            .iter()
+            .map(|(name, expr)| {
+                (
+                    name.to_string(),
+                    FieldDef {
+                        display_name: name.to_string(),
+                        expr: expr.map(|e| e.to_string()),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect();
+        ResolvedSchema {
+            fields,
+            ..Default::default()
+        }
+    }
+
+    fn row(pairs: &[(&str, &str)]) -> CsvRow {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_computed_fields_are_evaluated_in_dependency_order() {
+        // sim_params depends on value, which is itself computed
+        let schema = schema_with(&[
+            ("sim_params", Some("r={value}")),
+            ("value", Some("{resistance}")),
+            ("resistance", None),
+        ]);
+        let mut rows = vec![row(&[("id", "1"), ("resistance", "10k")])];
+        apply_computed_fields(&schema, &mut rows);
+
+        assert_eq!(rows[0]["value"], "10k");
+        assert_eq!(rows[0]["sim_params"], "r=10k");
+    }
+
+    #[test]
+    fn test_cycle_is_reported_and_skipped() {
+        let schema = schema_with(&[
+            ("a", Some("{b}")),
+            ("b", Some("{a}")),
+            ("c", Some("{a}")),
+            ("d", Some("x{resistance}")),
+            ("resistance", None),
+        ]);
+        let (order, cycle) = evaluation_order(&schema);
+        assert_eq!(order, vec!["d".to_string()]);
+        assert_eq!(
+            cycle,
+            Some(ComputedIssue::Cycle(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "c".to_string()
+            ]))
+        );
+
+        let mut rows = vec![row(&[("id", "1"), ("resistance", "1k")])];
+        apply_computed_fields(&schema, &mut rows);
+        assert!(!rows[0].contains_key("a"));
+        assert_eq!(rows[0]["d"], "x1k");
+    }
+
+    #[test]
+    fn test_self_reference_is_a_cycle() {
+        let schema = schema_with(&[("a", Some("{a}"))]);
+        let (order, cycle) = evaluation_order(&schema);
+        assert!(order.is_empty());
+        assert_eq!(cycle, Some(ComputedIssue::Cycle(vec!["a".to_string()])));
+    }
+
+    #[test]
+    fn test_unknown_reference_is_reported() {
+        let schema = schema_with(&[
+            ("value", Some("{resistance} {extra}")),
+            ("resistance", None),
+        ]);
+        let extra = "extra".to_string();
+        assert!(check_computed_fields(&schema, &[&extra]).is_empty());
+
+        let issues = check_computed_fields(&schema, &[]);
+        assert_eq!(
+            issues,
+            vec![ComputedIssue::UnknownReference {
+                field: "value".to_string(),
+                reference: "extra".to_string()
+            }]
+        );
+    }
+}
@@ -1,9 +1,11 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
 use indexmap::IndexMap;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use thiserror::Error;
 use tracing::warn;
-use uuid::Uuid;
+
+use crate::data::ids::IdGenerator;
 
 #[derive(Debug, Error)]
 pub enum CsvError {
@@ -13,42 +15,232 @@ pub enum CsvError {
     Csv(#[from] csv::Error),
     #[error("CSV file has no headers")]
     NoHeaders,
+    #[error("unsupported encoding {0}, save the file as UTF-8")]
+    UnsupportedEncoding(&'static str),
+    #[error("text can't be written as {0}, save the file as UTF-8")]
+    Unencodable(&'static str),
 }
 
 /// A single row of CSV data, preserving column order via IndexMap.
 pub type CsvRow = IndexMap<String, String>;
 
-/// Load a CSV file, ensuring every row has a unique `id`.
-/// Missing or duplicate IDs are auto-assigned and written back to disk.
-pub fn load_csv_with_ids(path: &Path) -> Result<Vec<CsvRow>, CsvError> {
-    let content = std::fs::read_to_string(path)?;
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .from_reader(content.as_bytes());
+/// UTF-8 byte order mark, as written by Excel's "CSV UTF-8" format.
+const BOM: char = '\u{feff}';
+
+/// Delimiters recognized when detecting a file's format.
+const DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+/// The dialect and encoding of a CSV file, detected on load so that writing
+/// the file back reproduces it and keeps diffs minimal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvFormat {
+    pub delimiter: u8,
+    pub quoting: Quoting,
+    /// Records end in `\r\n` rather than `\n`.
+    pub crlf: bool,
+    /// The file starts with a UTF-8 byte order mark.
+    pub bom: bool,
+    /// The last record ends with a line break.
+    pub final_newline: bool,
+    /// UTF-8, or Windows-1252 for files that aren't valid UTF-8 (typical of
+    /// Excel's "CSV (semicolon)" exports).
+    pub encoding: &'static Encoding,
+}
 
-    let headers: Vec<String> = reader
-        .headers()
-        .map_err(CsvError::Csv)?
-        .iter()
-        .map(|h| h.to_string())
-        .collect();
+/// Which fields are quoted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quoting {
+    /// Only fields that contain a delimiter, quote or line break.
+    #[default]
+    Necessary,
+    /// Every field.
+    Always,
+    /// Every field that isn't a number.
+    NonNumeric,
+}
 
-    if headers.is_empty() {
-        return Err(CsvError::NoHeaders);
+impl Default for CsvFormat {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quoting: Quoting::default(),
+            crlf: false,
+            bom: false,
+            final_newline: true,
+            encoding: UTF_8,
+        }
     }
+}
 
-    let has_id_column = headers.iter().any(|h| h == "id");
+impl CsvFormat {
+    /// Detect the format of decoded CSV `content` from its first two records.
+    /// The encoding is left as UTF-8; see `decode`.
+    pub fn detect(content: &str) -> Self {
+        let bom = content.starts_with(BOM);
+        let body = content.strip_prefix(BOM).unwrap_or(content);
+
+        let header = header_separators(body);
+        let delimiter = DELIMITERS
+            .into_iter()
+            .map(|d| (d, header.iter().filter(|&&b| b == d).count()))
+            // `max_by_key` keeps the last maximum; prefer the earlier candidate
+            .rev()
+            .max_by_key(|&(_, count)| count)
+            .filter(|&(_, count)| count > 0)
+            .map_or(b',', |(d, _)| d);
+
+        Self {
+            delimiter,
+            quoting: detect_quoting(body, delimiter),
+            crlf: body
+                .find('\n')
+                .is_some_and(|i| body[..i].ends_with('\r')),
+            bom,
+            final_newline: body.is_empty() || body.ends_with('\n'),
+            encoding: UTF_8,
+        }
+    }
 
-    let mut rows: Vec<CsvRow> = Vec::new();
-    for result in reader.records() {
-        let record = result?;
-        let mut row = IndexMap::new();
-        for (i, header) in headers.iter().enumerate() {
-            let value = record.get(i).unwrap_or("").to_string();
-            row.insert(header.clone(), value);
+    fn reader(&self) -> csv::ReaderBuilder {
+        let mut builder = csv::ReaderBuilder::new();
+        builder.has_headers(true).delimiter(self.delimiter);
+        builder
+    }
+
+    fn writer(&self) -> csv::WriterBuilder {
+        let mut builder = csv::WriterBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote_style(match self.quoting {
+                Quoting::Necessary => csv::QuoteStyle::Necessary,
+                Quoting::Always => csv::QuoteStyle::Always,
+                Quoting::NonNumeric => csv::QuoteStyle::NonNumeric,
+            })
+            .terminator(if self.crlf {
+                csv::Terminator::CRLF
+            } else {
+                csv::Terminator::Any(b'\n')
+            });
+        builder
+    }
+}
+
+/// Decode the bytes of a CSV file, detecting its format. Files that aren't
+/// valid UTF-8 are read as Windows-1252, which maps every byte, so writing
+/// them back in the same encoding leaves unchanged rows byte-for-byte equal.
+/// UTF-16 files are rejected: they can't be written back.
+pub fn decode(bytes: Vec<u8>) -> Result<(String, CsvFormat), CsvError> {
+    if let Some((encoding, _)) = Encoding::for_bom(&bytes).filter(|(e, _)| *e != UTF_8) {
+        return Err(CsvError::UnsupportedEncoding(encoding.name()));
+    }
+    let (content, encoding) = match String::from_utf8(bytes) {
+        Ok(content) => (content, UTF_8),
+        Err(e) => {
+            let (content, _) = WINDOWS_1252.decode_without_bom_handling(e.as_bytes());
+            (content.into_owned(), WINDOWS_1252)
+        }
+    };
+    let format = CsvFormat {
+        encoding,
+        ..CsvFormat::detect(&content)
+    };
+    Ok((content, format))
+}
+
+/// Read and decode the CSV file at `path`, see `decode`.
+pub fn read_text(path: &Path) -> Result<(String, CsvFormat), CsvError> {
+    decode(std::fs::read(path)?)
+}
+
+/// Detect the format of the CSV file at `path`, or the default format when
+/// it can't be read (e.g. it doesn't exist yet).
+pub fn read_format(path: &Path) -> CsvFormat {
+    read_text(path).map(|(_, format)| format).unwrap_or_default()
+}
+
+/// The bytes of the first line of `body` outside quoted fields, where the
+/// delimiter is counted. Line breaks in quotes don't end the line.
+fn header_separators(body: &str) -> Vec<u8> {
+    let mut in_quotes = false;
+    let mut separators = Vec::new();
+    for b in body.bytes() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            _ if in_quotes => {}
+            b'\n' | b'\r' => break,
+            _ => separators.push(b),
         }
-        rows.push(row);
     }
+    separators
+}
+
+/// Tell the quoting style from which fields of the first two records are quoted.
+fn detect_quoting(body: &str, delimiter: u8) -> Quoting {
+    let (mut quoted, mut numbers, mut others) = (0, 0, 0);
+    let mut field: Vec<u8> = Vec::new();
+    let (mut field_quoted, mut in_quotes, mut at_start) = (false, false, true);
+    let mut records = 0;
+
+    let mut finish = |field: &mut Vec<u8>, field_quoted: bool| {
+        if field_quoted {
+            quoted += 1;
+        } else if std::str::from_utf8(field).is_ok_and(|f| f.parse::<f64>().is_ok()) {
+            numbers += 1;
+        } else {
+            others += 1;
+        }
+        field.clear();
+    };
+    for &b in body.as_bytes() {
+        if in_quotes {
+            in_quotes = b != b'"';
+            continue;
+        }
+        match b {
+            b'"' if at_start => {
+                field_quoted = true;
+                in_quotes = true;
+            }
+            b'"' => in_quotes = true,
+            b'\r' => {}
+            b'\n' => {
+                finish(&mut field, field_quoted);
+                records += 1;
+                if records == 2 {
+                    break;
+                }
+            }
+            b if b == delimiter => finish(&mut field, field_quoted),
+            b => field.push(b),
+        }
+        at_start = matches!(b, b'\n') || b == delimiter;
+        if at_start {
+            field_quoted = false;
+        }
+    }
+    if records < 2 && !at_start {
+        finish(&mut field, field_quoted);
+    }
+
+    match (quoted, numbers, others) {
+        (0, _, _) => Quoting::Necessary,
+        (_, 0, 0) => Quoting::Always,
+        (_, _, 0) => Quoting::NonNumeric,
+        _ => Quoting::Necessary,
+    }
+}
+
+/// Load a CSV file, ensuring every row has a unique `id`.
+/// Missing or duplicate IDs are auto-assigned and written back to disk, using
+/// the ID strategy of the library the file belongs to (see `IdGenerator::for_csv`).
+pub fn load_csv_with_ids(path: &Path) -> Result<Vec<CsvRow>, CsvError> {
+    load_rows_with_ids(path).map(|(rows, _)| rows)
+}
+
+/// `load_csv_with_ids`, also returning the file's format for writing it back.
+fn load_rows_with_ids(path: &Path) -> Result<(Vec<CsvRow>, CsvFormat), CsvError> {
+    let (headers, mut rows, format) = read_rows(path)?;
+    let has_id_column = headers.iter().any(|h| h == "id");
 
     // Collect existing IDs to detect duplicates
     let mut used_ids: HashSet<String> = HashSet::new();
@@ -64,13 +256,25 @@ pub fn load_csv_with_ids(path: &Path) -> Result<Vec<CsvRow>, CsvError> {
         }
     }
 
+    // Only look up the library's ID settings when an ID is needed
+    let mut generator: Option<IdGenerator> = None;
+    let mut new_id = || {
+        generator
+            .get_or_insert_with(|| {
+                let mut generator = IdGenerator::for_csv(path);
+                generator.reserve(used_ids.iter().map(String::as_str));
+                generator
+            })
+            .next_id()
+    };
+
     // Assign IDs to rows that don't have one, or have duplicates
     let mut seen_ids: HashSet<String> = HashSet::new();
     for row in &mut rows {
         if !has_id_column {
             // Need to insert id as first column
             needs_writeback = true;
-            let id = Uuid::new_v4().to_string();
+            let id = new_id();
 
             // Rebuild row with id first
             let mut new_row = IndexMap::new();
@@ -86,10 +290,9 @@ pub fn load_csv_with_ids(path: &Path) -> Result<Vec<CsvRow>, CsvError> {
                 if !id.is_empty() {
                     warn!("duplicate id '{}' detected, assigning new id", id);
                 }
-                let new_id = Uuid::new_v4().to_string();
-                used_ids.insert(new_id.clone());
-                row.insert("id".to_string(), new_id.clone());
-                seen_ids.insert(new_id);
+                let id = new_id();
+                row.insert("id".to_string(), id.clone());
+                seen_ids.insert(id);
                 needs_writeback = true;
             } else {
                 seen_ids.insert(id);
@@ -98,28 +301,143 @@ pub fn load_csv_with_ids(path: &Path) -> Result<Vec<CsvRow>, CsvError> {
     }
 
     if needs_writeback {
-        write_csv(path, &rows)?;
+        write_csv_with_format(path, &rows, &format)?;
+    }
+
+    Ok((rows, format))
+}
+
+/// Load a CSV file without ever writing to it, for read-only checkouts.
+///
+/// Rows without an `id` get one derived from `table` and the row's content,
+/// kept in memory only: it stays the same across loads for as long as the
+/// row is unchanged. Duplicate IDs are reported and left as they are.
+pub fn load_csv_read_only(path: &Path, table: &str) -> Result<Vec<CsvRow>, CsvError> {
+    let (_, mut rows, _) = read_rows(path)?;
+
+    let mut seen_ids: HashSet<String> = HashSet::new();
+    let mut occurrences: HashMap<String, u64> = HashMap::new();
+    for row in &mut rows {
+        match row.get("id").filter(|id| !id.is_empty()) {
+            Some(id) => {
+                if !seen_ids.insert(id.clone()) {
+                    warn!("duplicate id '{}' in {}", id, path.display());
+                }
+            }
+            None => {
+                // Identical rows are told apart by how many came before
+                let first = derived_id(table, row, 0);
+                let occurrence = occurrences.entry(first.clone()).or_default();
+                let id = match *occurrence {
+                    0 => first,
+                    n => derived_id(table, row, n),
+                };
+                *occurrence += 1;
+                row.shift_remove("id");
+                row.shift_insert(0, "id".to_string(), id);
+            }
+        }
     }
 
     Ok(rows)
 }
 
+/// Read a CSV file into rows keyed by header, detecting its format.
+fn read_rows(path: &Path) -> Result<(Vec<String>, Vec<CsvRow>, CsvFormat), CsvError> {
+    let (content, format) = read_text(path)?;
+    let body = content.strip_prefix(BOM).unwrap_or(&content);
+    let mut reader = format.reader().from_reader(body.as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(CsvError::Csv)?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+
+    if headers.is_empty() {
+        return Err(CsvError::NoHeaders);
+    }
+
+    let mut rows: Vec<CsvRow> = Vec::new();
+    for result in reader.records() {
+        let record = result?;
+        let mut row = IndexMap::new();
+        for (i, header) in headers.iter().enumerate() {
+            let value = record.get(i).unwrap_or("").to_string();
+            row.insert(header.clone(), value);
+        }
+        rows.push(row);
+    }
+    Ok((headers, rows, format))
+}
+
+/// The non-empty `id` column values of CSV `content`, in any format.
+pub fn read_ids(content: &str) -> Vec<String> {
+    let format = CsvFormat::detect(content);
+    let body = content.strip_prefix(BOM).unwrap_or(content);
+    let mut reader = format.reader().from_reader(body.as_bytes());
+    let Some(id_column) = reader
+        .headers()
+        .ok()
+        .and_then(|headers| headers.iter().position(|h| h == "id"))
+    else {
+        return Vec::new();
+    };
+    reader
+        .records()
+        .flatten()
+        .filter_map(|record| record.get(id_column).map(str::to_string))
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// A UUID derived from a part table name and a row's content (without its
+/// `id`), using the 128-bit FNV-1a hash, which is stable across builds.
+fn derived_id(table: &str, row: &CsvRow, occurrence: u64) -> String {
+    const FNV_OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    let mut hash = FNV_OFFSET;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= u128::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+    feed(table.as_bytes());
+    feed(&[0]);
+    for (column, value) in row.iter().filter(|(column, _)| *column != "id") {
+        feed(column.as_bytes());
+        feed(&[0]);
+        feed(value.as_bytes());
+        feed(&[0]);
+    }
+    feed(&occurrence.to_le_bytes());
+    uuid::Builder::from_custom_bytes(hash.to_be_bytes())
+        .into_uuid()
+        .to_string()
+}
+
 /// Write rows back to a CSV file using temp file + rename for safety.
+/// The file keeps its current format; new files use the default one.
 pub fn write_csv(path: &Path, rows: &[CsvRow]) -> Result<(), CsvError> {
+    write_csv_with_format(path, rows, &read_format(path))
+}
+
+/// Write rows to a CSV file in the given format, using temp file + rename.
+pub fn write_csv_with_format(
+    path: &Path,
+    rows: &[CsvRow],
+    format: &CsvFormat,
+) -> Result<(), CsvError> {
     if rows.is_empty() {
         return Ok(());
     }
 
     let headers: Vec<String> = rows[0].keys().cloned().collect();
 
-    // Write to a temp file in the same directory, then rename
-    let parent = path.parent().unwrap_or(Path::new("."));
-    let temp_path = parent.join(format!(
-        ".{}.tmp",
-        path.file_name().unwrap_or_default().to_string_lossy()
-    ));
-
-    let mut writer = csv::WriterBuilder::new().from_path(&temp_path)?;
+    let mut writer = format.writer().from_writer(Vec::new());
     writer.write_record(&headers)?;
 
     for row in rows {
@@ -130,9 +448,35 @@ pub fn write_csv(path: &Path, rows: &[CsvRow]) -> Result<(), CsvError> {
         writer.write_record(&record)?;
     }
 
-    writer.flush()?;
-    drop(writer);
+    let mut data = writer.into_inner().map_err(|e| CsvError::Io(e.into_error()))?;
+    if !format.final_newline {
+        let terminator: &[u8] = if format.crlf { b"\r\n" } else { b"\n" };
+        if data.ends_with(terminator) {
+            data.truncate(data.len() - terminator.len());
+        }
+    }
+    if format.encoding != UTF_8 {
+        // The writer was given `&str`s only, so its output is UTF-8
+        let text = String::from_utf8_lossy(&data);
+        let (encoded, _, unmappable) = format.encoding.encode(&text);
+        if unmappable {
+            return Err(CsvError::Unencodable(format.encoding.name()));
+        }
+        data = encoded.into_owned();
+    }
+    if format.bom {
+        let mut bom = [0; 3];
+        BOM.encode_utf8(&mut bom);
+        data.splice(0..0, bom);
+    }
 
+    // Write to a temp file in the same directory, then rename
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let temp_path = parent.join(format!(
+        ".{}.tmp",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    std::fs::write(&temp_path, data)?;
     std::fs::rename(&temp_path, path)?;
 
     Ok(())
@@ -141,9 +485,11 @@ pub fn write_csv(path: &Path, rows: &[CsvRow]) -> Result<(), CsvError> {
 /// Append a new row to a CSV file, auto-assigning an ID.
 /// Returns the assigned ID.
 pub fn append_row(path: &Path, fields: &CsvRow) -> Result<String, CsvError> {
-    let mut rows = load_csv_with_ids(path)?;
+    let (mut rows, format) = load_rows_with_ids(path)?;
 
-    let new_id = Uuid::new_v4().to_string();
+    let mut generator = IdGenerator::for_csv(path);
+    generator.reserve(rows.iter().filter_map(|row| row.get("id")).map(String::as_str));
+    let new_id = generator.next_id();
 
     // Build new row with id first, then existing columns from first row as template
     let mut new_row = IndexMap::new();
@@ -165,13 +511,13 @@ pub fn append_row(path: &Path, fields: &CsvRow) -> Result<String, CsvError> {
     }
 
     rows.push(new_row);
-    write_csv(path, &rows)?;
+    write_csv_with_format(path, &rows, &format)?;
     Ok(new_id)
 }
 
 /// Update an existing row by ID.
 pub fn update_row(path: &Path, id: &str, fields: &CsvRow) -> Result<(), CsvError> {
-    let mut rows = load_csv_with_ids(path)?;
+    let (mut rows, format) = load_rows_with_ids(path)?;
 
     let row = rows
         .iter_mut()
@@ -184,7 +530,7 @@ pub fn update_row(path: &Path, id: &str, fields: &CsvRow) -> Result<(), CsvError
                     row.insert(key.clone(), value.clone());
                 }
             }
-            write_csv(path, &rows)?;
+            write_csv_with_format(path, &rows, &format)?;
             Ok(())
         }
         None => Err(row_not_found(id)),
@@ -192,13 +538,13 @@ pub fn update_row(path: &Path, id: &str, fields: &CsvRow) -> Result<(), CsvError
 }
 
 /// Rename columns in a CSV file. Each entry in `renames` is `(old_key, new_key)`.
-/// Columns not found are silently skipped. Writes back via `write_csv`.
+/// Columns not found are silently skipped. The file keeps its format.
 pub fn rename_csv_columns(path: &Path, renames: &[(String, String)]) -> Result<(), CsvError> {
     if renames.is_empty() || !path.exists() {
         return Ok(());
     }
 
-    let mut rows = load_csv_with_ids(path)?;
+    let (mut rows, format) = load_rows_with_ids(path)?;
     if rows.is_empty() {
         return Ok(());
     }
@@ -221,7 +567,7 @@ pub fn rename_csv_columns(path: &Path, renames: &[(String, String)]) -> Result<(
         }
     }
 
-    write_csv(path, &rows)
+    write_csv_with_format(path, &rows, &format)
 }
 
 /// Remove columns from a CSV file. Columns not found are silently skipped.
@@ -230,7 +576,7 @@ pub fn remove_csv_columns(path: &Path, columns: &[String]) -> Result<(), CsvErro
         return Ok(());
     }
 
-    let mut rows = load_csv_with_ids(path)?;
+    let (mut rows, format) = load_rows_with_ids(path)?;
     if rows.is_empty() {
         return Ok(());
     }
@@ -241,7 +587,7 @@ pub fn remove_csv_columns(path: &Path, columns: &[String]) -> Result<(), CsvErro
         row.retain(|key, _| !to_remove.contains(key.as_str()));
     }
 
-    write_csv(path, &rows)
+    write_csv_with_format(path, &rows, &format)
 }
 
 fn row_not_found(id: &str) -> CsvError {
@@ -266,7 +612,7 @@ pub fn delete_component(path: &Path, id: &str) -> Result<(), CsvError> {
 
 /// Delete a row by ID.
 pub fn delete_row(path: &Path, id: &str) -> Result<(), CsvError> {
-    let mut rows = load_csv_with_ids(path)?;
+    let (mut rows, format) = load_rows_with_ids(path)?;
     let original_len = rows.len();
     rows.retain(|r| r.get("id").map(|v| v.as_str()) != Some(id));
 
@@ -274,7 +620,7 @@ pub fn delete_row(path: &Path, id: &str) -> Result<(), CsvError> {
         return Err(row_not_found(id));
     }
 
-    write_csv(path, &rows)?;
+    write_csv_with_format(path, &rows, &format)?;
     Ok(())
 }
 
@@ -367,6 +713,66 @@ mod tests {
         assert!(content.starts_with("id,mpn,value"));
     }
 
+    #[test]
+    fn test_ids_follow_library_strategy() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("library.yaml"),
+            "name: lib\ntemplates_path: schemas\nids:\n  strategy: pattern\n  pattern: \"{prefix}-{counter:6}\"\npart_tables:\n  - file: resistors.csv\n    template: resistor\n    name: Resistors\n    id_prefix: RES\n",
+        )
+        .unwrap();
+        let csv_path = tmp.path().join("resistors.csv");
+        fs::write(
+            &csv_path,
+            "id,mpn\nRES-000122,A\n,B\nRES-000122,C\n",
+        )
+        .unwrap();
+
+        let rows = load_csv_with_ids(&csv_path).unwrap();
+        let ids: Vec<&str> = rows.iter().map(|r| r["id"].as_str()).collect();
+        assert_eq!(ids, ["RES-000122", "RES-000123", "RES-000124"]);
+
+        let mut fields = CsvRow::new();
+        fields.insert("mpn".to_string(), "D".to_string());
+        assert_eq!(append_row(&csv_path, &fields).unwrap(), "RES-000125");
+    }
+
+    #[test]
+    fn test_load_csv_read_only_derives_ids() {
+        let tmp = TempDir::new().unwrap();
+        let csv_path = tmp.path().join("test.csv");
+        let content = "mpn,value\nRC0603FR-0710KL,10K\nRC0603FR-0710KL,10K\nRC0603FR-07100KL,100K\n";
+        fs::write(&csv_path, content).unwrap();
+
+        let rows = load_csv_read_only(&csv_path, "Resistors").unwrap();
+        assert_eq!(fs::read_to_string(&csv_path).unwrap(), content);
+        let keys: Vec<&String> = rows[0].keys().collect();
+        assert_eq!(keys[0], "id");
+        // Identical rows still get distinct IDs
+        let ids: HashSet<&String> = rows.iter().map(|r| &r["id"]).collect();
+        assert_eq!(ids.len(), 3);
+
+        // Same IDs on every load, different ones for another table
+        let again = load_csv_read_only(&csv_path, "Resistors").unwrap();
+        assert_eq!(rows, again);
+        let other = load_csv_read_only(&csv_path, "Capacitors").unwrap();
+        assert_ne!(rows[0]["id"], other[0]["id"]);
+    }
+
+    #[test]
+    fn test_load_csv_read_only_keeps_duplicates() {
+        let tmp = TempDir::new().unwrap();
+        let csv_path = tmp.path().join("test.csv");
+        let content = "id,mpn,value\n1,RC0603FR-0710KL,10K\n1,RC0603FR-07100KL,100K\n,RC0603FR-071KL,1K\n";
+        fs::write(&csv_path, content).unwrap();
+
+        let rows = load_csv_read_only(&csv_path, "Resistors").unwrap();
+        assert_eq!(rows[0]["id"], "1");
+        assert_eq!(rows[1]["id"], "1");
+        assert!(!rows[2]["id"].is_empty());
+        assert_eq!(fs::read_to_string(&csv_path).unwrap(), content);
+    }
+
     #[test]
     fn test_rename_csv_columns() {
         let tmp = TempDir::new().unwrap();
@@ -471,4 +877,118 @@ mod tests {
         assert_eq!(rows[0]["description"], "RES 10K OHM 1% 1/10W 0603");
         assert_eq!(rows[1]["description"], "RES 100K OHM 1% 1/10W 0603");
     }
+
+    #[test]
+    fn test_detect_format() {
+        let excel = CsvFormat::detect("\u{feff}id;mpn;value\r\n1;RC0603;10K\r\n");
+        assert_eq!(
+            excel,
+            CsvFormat {
+                delimiter: b';',
+                quoting: Quoting::Necessary,
+                crlf: true,
+                bom: true,
+                final_newline: true,
+                encoding: UTF_8,
+            }
+        );
+
+        let quoted = CsvFormat::detect("\"id\"\t\"mpn\"\n\"1\"\t\"a,b\"");
+        assert_eq!(quoted.delimiter, b'\t');
+        assert_eq!(quoted.quoting, Quoting::Always);
+        assert!(!quoted.final_newline);
+
+        let non_numeric = CsvFormat::detect("\"id\",\"qty\"\n\"R1\",10\n");
+        assert_eq!(non_numeric.quoting, Quoting::NonNumeric);
+
+        assert_eq!(CsvFormat::detect("id,mpn\n1,\"a, b\"\n"), CsvFormat::default());
+
+        // Delimiters inside a quoted header name don't count
+        let quoted_header = CsvFormat::detect("\"value;tolerance\",mpn\n10K;1%,RC0603\n");
+        assert_eq!(quoted_header.delimiter, b',');
+        let quoted_header = CsvFormat::detect("\"value;tolerance;voltage\",mpn\n10K;1%;50V,RC0603\n");
+        assert_eq!(quoted_header.delimiter, b',');
+        let quoted_header = CsvFormat::detect("id;\"mpn, alt\";\"a,b\"\n1;RC0603;x\n");
+        assert_eq!(quoted_header.delimiter, b';');
+    }
+
+    #[test]
+    fn test_writes_preserve_excel_format() {
+        let tmp = TempDir::new().unwrap();
+        let csv_path = tmp.path().join("test.csv");
+        fs::write(
+            &csv_path,
+            "\u{feff}id;mpn;value\r\n;RC0603FR-0710KL;10K\r\n2;RC0603FR-07100KL;\"100K; 1%\"\r\n",
+        )
+        .unwrap();
+
+        // Assigning the missing ID only changes that field
+        let rows = load_csv_with_ids(&csv_path).unwrap();
+        assert_eq!(rows[1]["value"], "100K; 1%");
+        let expected = format!(
+            "\u{feff}id;mpn;value\r\n{};RC0603FR-0710KL;10K\r\n2;RC0603FR-07100KL;\"100K; 1%\"\r\n",
+            rows[0]["id"]
+        );
+        assert_eq!(fs::read_to_string(&csv_path).unwrap(), expected);
+
+        let mut fields = CsvRow::new();
+        fields.insert("mpn".to_string(), "X".to_string());
+        let id = append_row(&csv_path, &fields).unwrap();
+        assert_eq!(
+            fs::read_to_string(&csv_path).unwrap(),
+            format!("{expected}{id};X;\r\n")
+        );
+    }
+
+    #[test]
+    fn test_writes_preserve_quoting_and_missing_final_newline() {
+        let tmp = TempDir::new().unwrap();
+        let csv_path = tmp.path().join("test.csv");
+        let original = "\"id\",\"mpn\",\"value\"\n\"1\",\"RC0603FR-0710KL\",\"10K\"";
+        fs::write(&csv_path, original).unwrap();
+
+        update_row(&csv_path, "1", &CsvRow::from([("value".to_string(), "22K".to_string())])).unwrap();
+        assert_eq!(
+            fs::read_to_string(&csv_path).unwrap(),
+            original.replace("\"10K\"", "\"22K\"")
+        );
+    }
+
+    #[test]
+    fn test_windows_1252_files_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let csv_path = tmp.path().join("test.csv");
+        // "4.7µF" and "±10%" in Windows-1252, as saved by Excel's "CSV (semicolon)"
+        let original: &[u8] = b"id;value;tolerance\r\n1;4.7\xb5F;\xb110%\r\n2;10\xb5F;\xb120%\r\n";
+        fs::write(&csv_path, original).unwrap();
+
+        let rows = load_csv_with_ids(&csv_path).unwrap();
+        assert_eq!(rows[0]["value"], "4.7µF");
+        assert_eq!(rows[0]["tolerance"], "±10%");
+        assert_eq!(read_format(&csv_path).encoding, WINDOWS_1252);
+
+        update_row(&csv_path, "2", &CsvRow::from([("value".to_string(), "22µF".to_string())])).unwrap();
+        assert_eq!(
+            fs::read(&csv_path).unwrap(),
+            b"id;value;tolerance\r\n1;4.7\xb5F;\xb110%\r\n2;22\xb5F;\xb120%\r\n"
+        );
+
+        // Text Windows-1252 can't hold is refused rather than mangled
+        let err = update_row(&csv_path, "2", &CsvRow::from([("value".to_string(), "10Ω".to_string())]))
+            .unwrap_err();
+        assert!(matches!(err, CsvError::Unencodable("windows-1252")), "{err}");
+        assert!(fs::read(&csv_path).unwrap().ends_with(b"2;22\xb5F;\xb120%\r\n"));
+    }
+
+    #[test]
+    fn test_utf16_files_are_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let csv_path = tmp.path().join("test.csv");
+        let mut content = vec![0xff, 0xfe];
+        content.extend("id\tmpn\n1\tA\n".encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(&csv_path, content).unwrap();
+
+        let err = load_csv_with_ids(&csv_path).unwrap_err();
+        assert!(matches!(err, CsvError::UnsupportedEncoding("UTF-16LE")), "{err}");
+    }
 }
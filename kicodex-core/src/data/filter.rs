@@ -0,0 +1,299 @@
+use thiserror::Error;
+
+use crate::data::csv_loader::CsvRow;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FilterError {
+    #[error("unterminated string in filter")]
+    UnterminatedString,
+    #[error("unexpected end of filter, expected {0}")]
+    UnexpectedEnd(&'static str),
+    #[error("unexpected '{found}' in filter, expected {expected}")]
+    Unexpected {
+        found: String,
+        expected: &'static str,
+    },
+}
+
+/// A parsed row filter expression.
+///
+/// Syntax: comparisons `field == value`, `field != value` and
+/// `field contains value`, combined with `and`, `or`, `not` and parentheses.
+/// Values are bare words (`0402`, `1%`) or quoted strings (`"Yageo America"`).
+/// Comparisons ignore case, and a missing column compares as empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    Eq(String, String),
+    Ne(String, String),
+    Contains(String, String),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+impl Filter {
+    /// Parse a filter expression.
+    pub fn parse(input: &str) -> Result<Filter, FilterError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let filter = parser.or_expr()?;
+        match parser.next() {
+            None => Ok(filter),
+            Some(tok) => Err(FilterError::Unexpected {
+                found: tok.to_string(),
+                expected: "end of filter",
+            }),
+        }
+    }
+
+    /// Whether a CSV row matches this filter.
+    pub fn matches(&self, row: &CsvRow) -> bool {
+        let value = |field: &str| row.get(field).map(|v| v.trim().to_lowercase()).unwrap_or_default();
+        match self {
+            Filter::Eq(field, expected) => value(field) == expected.to_lowercase(),
+            Filter::Ne(field, expected) => value(field) != expected.to_lowercase(),
+            Filter::Contains(field, needle) => value(field).contains(&needle.to_lowercase()),
+            Filter::Not(inner) => !inner.matches(row),
+            Filter::And(a, b) => a.matches(row) && b.matches(row),
+            Filter::Or(a, b) => a.matches(row) || b.matches(row),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Eq,
+    Ne,
+    LParen,
+    RParen,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(w) => f.write_str(w),
+            Token::Quoted(q) => write!(f, "\"{}\"", q),
+            Token::Eq => f.write_str("=="),
+            Token::Ne => f.write_str("!="),
+            Token::LParen => f.write_str("("),
+            Token::RParen => f.write_str(")"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some(ch) if ch == c => break,
+                        Some(ch) => s.push(ch),
+                        None => return Err(FilterError::UnterminatedString),
+                    }
+                }
+                tokens.push(Token::Quoted(s));
+            }
+            '=' | '!' => {
+                chars.next();
+                if chars.next_if_eq(&'=').is_none() {
+                    return Err(FilterError::Unexpected {
+                        found: c.to_string(),
+                        expected: "'==' or '!='",
+                    });
+                }
+                tokens.push(if c == '=' { Token::Eq } else { Token::Ne });
+            }
+            _ => {
+                let mut s = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || matches!(ch, '(' | ')' | '"' | '\'' | '=' | '!') {
+                        break;
+                    }
+                    s.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(s));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let tok = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn or_expr(&mut self) -> Result<Filter, FilterError> {
+        let mut left = self.and_expr()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            let right = self.and_expr()?;
+            left = Filter::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Filter, FilterError> {
+        let mut left = self.not_expr()?;
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            let right = self.not_expr()?;
+            left = Filter::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> Result<Filter, FilterError> {
+        if self.peek_keyword("not") {
+            self.pos += 1;
+            return Ok(Filter::Not(Box::new(self.not_expr()?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let inner = self.or_expr()?;
+            return match self.next() {
+                Some(Token::RParen) => Ok(inner),
+                Some(tok) => Err(FilterError::Unexpected {
+                    found: tok.to_string(),
+                    expected: "')'",
+                }),
+                None => Err(FilterError::UnexpectedEnd("')'")),
+            };
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Filter, FilterError> {
+        let field = match self.next() {
+            Some(Token::Word(w)) => w,
+            Some(tok) => {
+                return Err(FilterError::Unexpected {
+                    found: tok.to_string(),
+                    expected: "a field name",
+                })
+            }
+            None => return Err(FilterError::UnexpectedEnd("a field name")),
+        };
+        let op = match self.next() {
+            Some(tok @ (Token::Eq | Token::Ne)) => tok,
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("contains") => Token::Word(w),
+            Some(tok) => {
+                return Err(FilterError::Unexpected {
+                    found: tok.to_string(),
+                    expected: "'==', '!=' or 'contains'",
+                })
+            }
+            None => return Err(FilterError::UnexpectedEnd("'==', '!=' or 'contains'")),
+        };
+        let value = match self.next() {
+            Some(Token::Word(w) | Token::Quoted(w)) => w,
+            Some(tok) => {
+                return Err(FilterError::Unexpected {
+                    found: tok.to_string(),
+                    expected: "a value",
+                })
+            }
+            None => return Err(FilterError::UnexpectedEnd("a value")),
+        };
+        Ok(match op {
+            Token::Eq => Filter::Eq(field, value),
+            Token::Ne => Filter::Ne(field, value),
+            _ => Filter::Contains(field, value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(pairs: &[(&str, &str)]) -> CsvRow {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_simple_comparisons() {
+        let r = row(&[("package", "0402"), ("manufacturer", "Yageo America")]);
+        assert!(Filter::parse("package == 0402").unwrap().matches(&r));
+        assert!(!Filter::parse("package != 0402").unwrap().matches(&r));
+        assert!(Filter::parse("manufacturer == \"yageo america\"").unwrap().matches(&r));
+        assert!(Filter::parse("manufacturer contains yageo").unwrap().matches(&r));
+        // Missing columns compare as empty
+        assert!(Filter::parse("grade == ''").unwrap().matches(&r));
+        assert!(!Filter::parse("grade == automotive").unwrap().matches(&r));
+    }
+
+    #[test]
+    fn test_boolean_operators_and_precedence() {
+        let f = Filter::parse("package == 0402 or package == 0201 and grade == automotive").unwrap();
+        // `and` binds tighter than `or`
+        assert!(f.matches(&row(&[("package", "0402")])));
+        assert!(!f.matches(&row(&[("package", "0201")])));
+        assert!(f.matches(&row(&[("package", "0201"), ("grade", "Automotive")])));
+
+        let f = Filter::parse("not (package == 0402 or package == 0201)").unwrap();
+        assert!(f.matches(&row(&[("package", "0603")])));
+        assert!(!f.matches(&row(&[("package", "0201")])));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Filter::parse("package = 0402"),
+            Err(FilterError::Unexpected {
+                found: "=".to_string(),
+                expected: "'==' or '!='"
+            })
+        );
+        assert_eq!(
+            Filter::parse("package =="),
+            Err(FilterError::UnexpectedEnd("a value"))
+        );
+        assert_eq!(
+            Filter::parse("(package == 0402"),
+            Err(FilterError::UnexpectedEnd("')'"))
+        );
+        assert_eq!(
+            Filter::parse("name == \"open"),
+            Err(FilterError::UnterminatedString)
+        );
+        assert!(Filter::parse("package == 0402 extra").is_err());
+    }
+}
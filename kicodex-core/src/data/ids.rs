@@ -0,0 +1,536 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::data::{csv_loader, library};
+
+/// Pattern used by the `pattern` strategy when library.yaml sets none.
+pub const DEFAULT_ID_PATTERN: &str = "{prefix}-{counter:6}";
+
+/// Crockford's base32 alphabet, used by ULIDs and short IDs.
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Length of `short` IDs: 10 base32 characters, 50 random bits.
+const SHORT_ID_LEN: usize = 10;
+
+/// How new part row IDs are generated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdStrategy {
+    /// Random UUIDs (v4).
+    #[default]
+    Uuid,
+    /// ULIDs: sortable by creation time, 26 characters.
+    Ulid,
+    /// 10 random base32 characters.
+    Short,
+    /// Per-table prefix and counter, e.g. `RES-000123`. See `IdConfig::pattern`.
+    Pattern,
+}
+
+/// Row ID settings of a library, from `ids` in library.yaml.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdConfig {
+    #[serde(default)]
+    pub strategy: IdStrategy,
+    /// Format of `pattern` IDs. `{prefix}` is the part table's `id_prefix`
+    /// and `{counter}` the next free number, zero-padded with `{counter:N}`.
+    /// Defaults to `DEFAULT_ID_PATTERN`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+impl IdConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Generates row IDs for one part table, never returning an ID already taken.
+#[derive(Debug)]
+pub struct IdGenerator {
+    strategy: IdStrategy,
+    counter: CounterPattern,
+    next_counter: u64,
+    used: HashSet<String>,
+}
+
+/// A pattern with its prefix filled in, split around `{counter}`.
+#[derive(Debug)]
+struct CounterPattern {
+    before: String,
+    after: String,
+    width: usize,
+}
+
+impl CounterPattern {
+    fn parse(pattern: &str, prefix: &str) -> Option<Self> {
+        let pattern = pattern.replace("{prefix}", prefix);
+        let start = pattern.find("{counter")?;
+        let end = start + pattern[start..].find('}')?;
+        let width = match &pattern[start + "{counter".len()..end] {
+            "" => 0,
+            spec => spec.strip_prefix(':')?.parse().ok()?,
+        };
+        Some(Self {
+            before: pattern[..start].to_string(),
+            after: pattern[end + 1..].to_string(),
+            width,
+        })
+    }
+
+    fn format(&self, counter: u64) -> String {
+        format!("{}{:0width$}{}", self.before, counter, self.after, width = self.width)
+    }
+
+    /// The counter of an ID produced by this pattern.
+    fn counter_of(&self, id: &str) -> Option<u64> {
+        let digits = id.strip_prefix(&self.before)?.strip_suffix(&self.after)?;
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+}
+
+impl IdGenerator {
+    /// A generator for a part table whose `pattern` IDs start with `prefix`.
+    pub fn new(config: &IdConfig, prefix: &str) -> Self {
+        let pattern = config.pattern.as_deref().unwrap_or(DEFAULT_ID_PATTERN);
+        let counter = CounterPattern::parse(pattern, prefix).unwrap_or_else(|| {
+            tracing::warn!(
+                "ID pattern '{}' has no valid {{counter}}, using '{}'",
+                pattern,
+                DEFAULT_ID_PATTERN
+            );
+            CounterPattern::parse(DEFAULT_ID_PATTERN, prefix).expect("default pattern is valid")
+        });
+        Self {
+            strategy: config.strategy,
+            counter,
+            next_counter: 1,
+            used: HashSet::new(),
+        }
+    }
+
+    /// The generator for the part table stored in `csv_path`, configured by
+    /// the library.yaml in the nearest parent directory. Without one, or when
+    /// the file is not a part table of it, the default strategy is used.
+    ///
+    /// Part tables with the same `id_prefix` share one `pattern` counter, so
+    /// it continues past the IDs of all of them. It also continues past their
+    /// IDs on the local git branches and their upstreams, so rows added on
+    /// different branches don't collide when merged. Only branches present
+    /// in this clone are seen: rows added on a branch that was never fetched
+    /// can still collide, and are given new IDs after the merge.
+    pub fn for_csv(csv_path: &Path) -> Self {
+        let Some(settings) = table_id_settings(csv_path) else {
+            return Self::new(&IdConfig::default(), "");
+        };
+        let mut generator = Self::new(&settings.config, &settings.prefix);
+        if generator.strategy == IdStrategy::Pattern {
+            let csv_path = csv_path.canonicalize().unwrap_or_else(|_| csv_path.to_path_buf());
+            for (_, path) in settings.files.iter().filter(|(_, path)| *path != csv_path) {
+                if let Ok((content, _)) = csv_loader::read_text(path) {
+                    generator.reserve(csv_loader::read_ids(&content).iter().map(String::as_str));
+                }
+            }
+            let files: Vec<&str> = settings.files.iter().map(|(file, _)| file.as_str()).collect();
+            let branch_ids = git_branch_ids(&settings.library_root, &files);
+            generator.reserve(branch_ids.iter().map(String::as_str));
+        }
+        generator
+    }
+
+    /// Mark IDs as taken. `pattern` counters continue after the highest one.
+    pub fn reserve<'a>(&mut self, ids: impl IntoIterator<Item = &'a str>) {
+        for id in ids {
+            if let Some(counter) = self.counter.counter_of(id) {
+                self.next_counter = self.next_counter.max(counter.saturating_add(1));
+            }
+            self.used.insert(id.to_string());
+        }
+    }
+
+    /// Generate an unused ID and mark it as taken.
+    pub fn next_id(&mut self) -> String {
+        loop {
+            let id = match self.strategy {
+                IdStrategy::Uuid => Uuid::new_v4().to_string(),
+                IdStrategy::Ulid => ulid(),
+                IdStrategy::Short => short_id(),
+                IdStrategy::Pattern => {
+                    let id = self.counter.format(self.next_counter);
+                    self.next_counter += 1;
+                    id
+                }
+            };
+            if self.used.insert(id.clone()) {
+                return id;
+            }
+        }
+    }
+}
+
+/// Random bits from the system RNG, by way of a v4 UUID (122 random bits).
+fn random_bits() -> u128 {
+    Uuid::new_v4().as_u128()
+}
+
+/// Encode the low `len * 5` bits of `value` in Crockford's base32.
+fn crockford(value: u128, len: usize) -> String {
+    (0..len)
+        .rev()
+        .map(|i| CROCKFORD[((value >> (i * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+/// A ULID: 48-bit millisecond timestamp followed by 80 random bits.
+fn ulid() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+        & ((1 << 48) - 1);
+    let random = random_bits() & ((1 << 80) - 1);
+    crockford((millis << 80) | random, 26)
+}
+
+fn short_id() -> String {
+    crockford(random_bits(), SHORT_ID_LEN).to_lowercase()
+}
+
+/// ID settings of the part table stored in a CSV file.
+struct TableIdSettings {
+    config: IdConfig,
+    prefix: String,
+    library_root: PathBuf,
+    /// Every part table file using `prefix`, this one included: the path
+    /// from library.yaml and the canonical path.
+    files: Vec<(String, PathBuf)>,
+}
+
+fn table_id_settings(csv_path: &Path) -> Option<TableIdSettings> {
+    let library_root = csv_path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("library.yaml").is_file())?;
+    let manifest = library::load_library_manifest(library_root).ok()?;
+    let csv_path = csv_path.canonicalize().ok()?;
+    let files: Vec<(String, PathBuf, String)> = manifest
+        .part_tables
+        .iter()
+        .filter_map(|def| {
+            let path = library_root.join(&def.file).canonicalize().ok()?;
+            Some((def.file.clone(), path, def.id_prefix()))
+        })
+        .collect();
+    let prefix = files.iter().find(|(_, path, _)| *path == csv_path)?.2.clone();
+    Some(TableIdSettings {
+        config: manifest.ids.clone(),
+        library_root: library_root.to_path_buf(),
+        files: files
+            .into_iter()
+            .filter(|(_, _, p)| *p == prefix)
+            .map(|(file, path, _)| (file, path))
+            .collect(),
+        prefix,
+    })
+}
+
+/// IDs found by `git_branch_ids`, per library root.
+static BRANCH_IDS: LazyLock<Mutex<HashMap<PathBuf, CachedBranchIds>>> =
+    LazyLock::new(Default::default);
+
+struct CachedBranchIds {
+    /// The branch tips and files the IDs were read from.
+    key: String,
+    ids: Vec<String>,
+}
+
+/// Row IDs of the part table `files` (relative to `library_root`) on the
+/// local git branches and their upstreams. Remote branches nothing tracks
+/// are skipped. Empty when the library is not in a git repository or git is
+/// unavailable.
+///
+/// Runs one `git for-each-ref`, and reads the files with one `git cat-file`
+/// only when a branch tip moved since the last call.
+fn git_branch_ids(library_root: &Path, files: &[&str]) -> Vec<String> {
+    let Some(refs) = Command::new("git")
+        .arg("-C")
+        .arg(library_root)
+        .args([
+            "for-each-ref",
+            "--format=%(objectname) %(refname) %(upstream)",
+            "refs/heads",
+            "refs/remotes",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+    else {
+        return Vec::new();
+    };
+    let refs = String::from_utf8_lossy(&refs.stdout);
+    let refs: Vec<(&str, &str, &str)> = refs
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            Some((fields.next()?, fields.next()?, fields.next().unwrap_or_default()))
+        })
+        .collect();
+    let upstreams: HashSet<&str> = refs
+        .iter()
+        .filter(|(_, name, _)| name.starts_with("refs/heads/"))
+        .map(|(_, _, upstream)| *upstream)
+        .collect();
+    let branches: Vec<(&str, &str)> = refs
+        .iter()
+        .filter(|(_, name, _)| name.starts_with("refs/heads/") || upstreams.contains(name))
+        .map(|(oid, name, _)| (*oid, *name))
+        .collect();
+
+    let key = format!("{:?} {:?}", branches, files);
+    let mut cache = BRANCH_IDS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(cached) = cache.get(library_root).filter(|cached| cached.key == key) {
+        return cached.ids.clone();
+    }
+
+    let specs: String = branches
+        .iter()
+        .flat_map(|(_, name)| {
+            files
+                .iter()
+                .map(move |file| format!("{}:./{}\n", name, file.replace('\\', "/")))
+        })
+        .collect();
+    let ids: Vec<String> = cat_files(library_root, specs)
+        .into_iter()
+        .filter_map(|content| csv_loader::decode(content).ok())
+        .flat_map(|(content, _)| csv_loader::read_ids(&content))
+        .collect();
+    cache.insert(
+        library_root.to_path_buf(),
+        CachedBranchIds {
+            key,
+            ids: ids.clone(),
+        },
+    );
+    ids
+}
+
+/// Contents of the blobs named by `specs` (one `rev:path` per line), read
+/// with `git cat-file --batch`. Missing blobs are skipped.
+fn cat_files(dir: &Path, specs: String) -> Vec<Vec<u8>> {
+    let Ok(mut child) = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return Vec::new();
+    };
+    // Write from another thread so a full stdout pipe can't deadlock us
+    let stdin = child.stdin.take();
+    let writer = std::thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(specs.as_bytes());
+        }
+    });
+    let output = child.wait_with_output();
+    let _ = writer.join();
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+
+    // Each blob is "<oid> blob <size>\n<content>\n"; others are "<spec> missing\n"
+    let mut blobs = Vec::new();
+    let mut rest = output.stdout.as_slice();
+    while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+        let header = String::from_utf8_lossy(&rest[..end]);
+        rest = &rest[end + 1..];
+        let mut fields = header.split(' ');
+        let (Some(_), Some(kind), Some(size)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let Ok(size) = size.parse::<usize>() else {
+            continue;
+        };
+        let Some(content) = rest.get(..size) else {
+            break;
+        };
+        if kind == "blob" {
+            blobs.push(content.to_vec());
+        }
+        rest = rest.get(size + 1..).unwrap_or_default();
+    }
+    blobs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn config(strategy: IdStrategy, pattern: Option<&str>) -> IdConfig {
+        IdConfig {
+            strategy,
+            pattern: pattern.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_pattern_ids_continue_after_highest_counter() {
+        let mut generator = IdGenerator::new(&config(IdStrategy::Pattern, None), "RES");
+        generator.reserve(["RES-000041", "RES-000007", "CAP-000900", "legacy-uuid"]);
+        assert_eq!(generator.next_id(), "RES-000042");
+        assert_eq!(generator.next_id(), "RES-000043");
+
+        let mut generator =
+            IdGenerator::new(&config(IdStrategy::Pattern, Some("P{counter}/{prefix}")), "R");
+        assert_eq!(generator.next_id(), "P1/R");
+    }
+
+    #[test]
+    fn test_invalid_pattern_falls_back_to_default() {
+        let mut generator = IdGenerator::new(&config(IdStrategy::Pattern, Some("{prefix}")), "RES");
+        assert_eq!(generator.next_id(), "RES-000001");
+    }
+
+    #[test]
+    fn test_random_strategies() {
+        let mut ulids = IdGenerator::new(&config(IdStrategy::Ulid, None), "");
+        let first = ulids.next_id();
+        assert_eq!(first.len(), 26);
+        assert!(first.bytes().all(|b| CROCKFORD.contains(&b)));
+        assert_ne!(ulids.next_id(), first);
+
+        let mut short = IdGenerator::new(&config(IdStrategy::Short, None), "");
+        let id = short.next_id();
+        assert_eq!(id.len(), SHORT_ID_LEN);
+        assert!(id.bytes().all(|b| b.is_ascii_digit() || b.is_ascii_lowercase()));
+    }
+
+    #[test]
+    fn test_for_csv_reads_library_settings() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("data")).unwrap();
+        fs::write(
+            tmp.path().join("library.yaml"),
+            r#"name: lib
+templates_path: schemas
+ids:
+  strategy: pattern
+part_tables:
+  - file: data/resistors.csv
+    template: resistor
+    name: Resistors
+    id_prefix: RES
+  - file: data/capacitors.csv
+    template: capacitor
+    name: Capacitors
+"#,
+        )
+        .unwrap();
+        for file in ["resistors.csv", "capacitors.csv"] {
+            fs::write(tmp.path().join("data").join(file), "id,mpn\n").unwrap();
+        }
+
+        let data = tmp.path().join("data");
+        assert_eq!(IdGenerator::for_csv(&data.join("resistors.csv")).next_id(), "RES-000001");
+        // The prefix defaults to the template name
+        assert_eq!(IdGenerator::for_csv(&data.join("capacitors.csv")).next_id(), "CAPACITOR-000001");
+    }
+
+    #[test]
+    fn test_tables_sharing_a_prefix_share_the_counter() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("library.yaml"),
+            r#"name: lib
+templates_path: schemas
+ids:
+  strategy: pattern
+part_tables:
+  - file: smd_resistors.csv
+    template: resistor
+    name: SMD Resistors
+  - file: tht_resistors.csv
+    template: resistor
+    name: THT Resistors
+  - file: capacitors.csv
+    template: capacitor
+    name: Capacitors
+"#,
+        )
+        .unwrap();
+        fs::write(
+            tmp.path().join("smd_resistors.csv"),
+            "id,mpn\nRESISTOR-000001,A\nRESISTOR-000002,B\n",
+        )
+        .unwrap();
+        fs::write(tmp.path().join("tht_resistors.csv"), "id,mpn\n").unwrap();
+        fs::write(tmp.path().join("capacitors.csv"), "id,mpn\nCAPACITOR-000009,C\n").unwrap();
+
+        let mut tht = IdGenerator::for_csv(&tmp.path().join("tht_resistors.csv"));
+        assert_eq!(tht.next_id(), "RESISTOR-000003");
+        let mut smd = IdGenerator::for_csv(&tmp.path().join("smd_resistors.csv"));
+        smd.reserve(["RESISTOR-000001", "RESISTOR-000002"]);
+        assert_eq!(smd.next_id(), "RESISTOR-000003");
+    }
+
+    #[test]
+    fn test_pattern_counter_skips_ids_on_other_branches() {
+        let tmp = TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(tmp.path())
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .output()
+                .is_ok_and(|output| output.status.success())
+        };
+        if !git(&["init", "-q", "-b", "main"]) {
+            // git is not available
+            return;
+        }
+        fs::write(
+            tmp.path().join("library.yaml"),
+            "name: lib\ntemplates_path: schemas\nids:\n  strategy: pattern\npart_tables:\n  - file: parts.csv\n    template: resistor\n    name: Resistors\n    id_prefix: RES\n",
+        )
+        .unwrap();
+        let csv_path = tmp.path().join("parts.csv");
+        fs::write(&csv_path, "id,mpn\nRES-000001,A\n").unwrap();
+        assert!(git(&["add", "."]) && git(&["commit", "-qm", "base"]));
+        assert!(git(&["checkout", "-qb", "feature"]));
+        fs::write(&csv_path, "id,mpn\nRES-000001,A\nRES-000002,B\nRES-000003,C\n").unwrap();
+        assert!(git(&["commit", "-qam", "feature"]));
+        // A remote branch is only seen when a local branch tracks it
+        fs::write(&csv_path, "id,mpn\nRES-000001,A\nRES-000009,D\n").unwrap();
+        assert!(git(&["commit", "-qam", "remote"]));
+        assert!(git(&["update-ref", "refs/remotes/origin/main", "HEAD"]));
+        assert!(git(&["reset", "-q", "--hard", "HEAD~1"]));
+        assert!(git(&["checkout", "-q", "main"]));
+
+        let mut generator = IdGenerator::for_csv(&csv_path);
+        generator.reserve(["RES-000001"]);
+        assert_eq!(generator.next_id(), "RES-000004");
+
+        assert!(git(&["config", "remote.origin.url", "."]));
+        assert!(git(&["config", "remote.origin.fetch", "+refs/heads/*:refs/remotes/origin/*"]));
+        assert!(git(&["branch", "-q", "--set-upstream-to", "origin/main"]));
+        let mut generator = IdGenerator::for_csv(&csv_path);
+        generator.reserve(["RES-000001"]);
+        assert_eq!(generator.next_id(), "RES-000010");
+    }
+}
@@ -1,7 +1,10 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashSet;
 use std::path::Path;
 use thiserror::Error;
 
+use crate::data::ids::IdConfig;
+
 #[derive(Debug, Error)]
 pub enum LibraryError {
     #[error("failed to read library.yaml: {0}")]
@@ -19,14 +22,255 @@ pub struct LibraryManifest {
     pub templates_path: String,
     #[serde(alias = "tables", alias = "component_types")]
     pub part_tables: Vec<PartTableDef>,
+    /// Virtual categories built by filtering rows of one or more part tables.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub views: Vec<ViewDef>,
+    /// How new part row IDs are generated.
+    #[serde(default, skip_serializing_if = "IdConfig::is_default")]
+    pub ids: IdConfig,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PartTableDef {
+    /// Stable category ID served to KiCad. Assigned and written back on load
+    /// when missing, so reordering `part_tables` never remaps categories.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_id"
+    )]
+    pub id: Option<String>,
     pub file: String,
     #[serde(alias = "schema")]
     pub template: String,
     pub name: String,
+    /// Short description shown in KiCad's symbol chooser.
+    /// Falls back to the template's `description` when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Longer free-form notes about the part table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Prefix of row IDs generated with the `pattern` ID strategy, e.g.
+    /// `RES`. Defaults to the upper-cased template name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_prefix: Option<String>,
+}
+
+impl PartTableDef {
+    /// Prefix of `pattern` row IDs of this table.
+    pub fn id_prefix(&self) -> String {
+        self.id_prefix
+            .clone()
+            .unwrap_or_else(|| self.template.to_uppercase())
+    }
+}
+
+/// A virtual category: the rows of `part_tables` that match `filter`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ViewDef {
+    /// Stable category ID, shared with the part table ID space.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_id"
+    )]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Names of the part tables to draw rows from. Empty means all of them.
+    #[serde(default, alias = "tables", skip_serializing_if = "Vec::is_empty")]
+    pub part_tables: Vec<String>,
+    /// Row filter expression, see `filter::Filter`.
+    #[serde(alias = "where")]
+    pub filter: String,
+}
+
+/// A part table whose ID differs between two versions of a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartTableIdChange {
+    pub name: String,
+    pub file: String,
+    pub old_id: String,
+    pub new_id: String,
+}
+
+/// A view whose ID differs between two versions of a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewIdChange {
+    pub name: String,
+    pub old_id: String,
+    pub new_id: String,
+}
+
+/// Accept both `id: 3` and `id: "3"` in library.yaml.
+fn deserialize_id<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawId {
+        Str(String),
+        Int(i64),
+    }
+
+    Ok(Option::<RawId>::deserialize(deserializer)?.map(|raw| match raw {
+        RawId::Str(s) => s,
+        RawId::Int(i) => i.to_string(),
+    }))
+}
+
+impl LibraryManifest {
+    /// Return an unused category ID: one past the highest numeric part table
+    /// or view ID in use, counting the IDs that tables and views without one
+    /// will be assigned on load.
+    pub fn next_part_table_id(&self) -> String {
+        let mut assigned = self.clone();
+        assign_part_table_ids(&mut assigned);
+        assign_view_ids(&mut assigned);
+        let max = assigned
+            .part_tables
+            .iter()
+            .filter_map(|t| t.id.as_deref())
+            .chain(assigned.views.iter().filter_map(|v| v.id.as_deref()))
+            .filter_map(|id| id.parse::<u64>().ok())
+            .max()
+            .unwrap_or(0);
+        (max + 1).to_string()
+    }
+}
+
+/// Assign IDs to part tables that have none, or whose ID duplicates an earlier table.
+/// Returns true if any ID was assigned.
+///
+/// A table without an ID first tries its 1-based position, so libraries that
+/// predate explicit IDs keep the category IDs KiCad has already cached.
+/// Otherwise the lowest unused integer is taken. View IDs count as used, so a
+/// new table never takes a category ID KiCad already knows as a view.
+pub fn assign_part_table_ids(manifest: &mut LibraryManifest) -> bool {
+    let mut used: HashSet<String> = manifest
+        .part_tables
+        .iter()
+        .filter_map(|t| t.id.clone())
+        .chain(manifest.views.iter().filter_map(|v| v.id.clone()))
+        .filter(|id| !id.is_empty())
+        .collect();
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut changed = false;
+
+    for (i, def) in manifest.part_tables.iter_mut().enumerate() {
+        if let Some(id) = def.id.as_deref().filter(|id| !id.is_empty()) {
+            if seen.insert(id.to_string()) {
+                continue;
+            }
+            tracing::warn!(
+                "duplicate part table id '{}' on '{}', assigning new id",
+                id,
+                def.name
+            );
+        }
+
+        let positional = (i + 1).to_string();
+        let new_id = if !used.contains(&positional) {
+            positional
+        } else {
+            (1u64..)
+                .map(|n| n.to_string())
+                .find(|candidate| !used.contains(candidate))
+                .unwrap()
+        };
+        used.insert(new_id.clone());
+        seen.insert(new_id.clone());
+        def.id = Some(new_id);
+        changed = true;
+    }
+
+    changed
+}
+
+/// Assign IDs to views that have none, or whose ID is already taken by a part
+/// table or an earlier view. Returns true if any ID was assigned.
+pub fn assign_view_ids(manifest: &mut LibraryManifest) -> bool {
+    let mut used: HashSet<String> = manifest
+        .part_tables
+        .iter()
+        .filter_map(|t| t.id.clone())
+        .chain(manifest.views.iter().filter_map(|v| v.id.clone()))
+        .filter(|id| !id.is_empty())
+        .collect();
+    let table_ids: HashSet<String> = manifest
+        .part_tables
+        .iter()
+        .filter_map(|t| t.id.clone())
+        .collect();
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut changed = false;
+
+    for view in manifest.views.iter_mut() {
+        if let Some(id) = view.id.as_deref().filter(|id| !id.is_empty()) {
+            if !table_ids.contains(id) && seen.insert(id.to_string()) {
+                continue;
+            }
+            tracing::warn!("view id '{}' on '{}' is already taken, assigning new id", id, view.name);
+        }
+
+        let new_id = (1u64..)
+            .map(|n| n.to_string())
+            .find(|candidate| !used.contains(candidate))
+            .unwrap();
+        used.insert(new_id.clone());
+        seen.insert(new_id.clone());
+        view.id = Some(new_id);
+        changed = true;
+    }
+
+    changed
+}
+
+/// List part tables whose ID changed between `old` and `new`, matching tables by CSV file.
+/// Tables without an ID in either version are not reported.
+pub fn part_table_id_changes(old: &LibraryManifest, new: &LibraryManifest) -> Vec<PartTableIdChange> {
+    new.part_tables
+        .iter()
+        .filter_map(|def| {
+            let new_id = def.id.as_ref()?;
+            let old_id = old
+                .part_tables
+                .iter()
+                .find(|t| t.file == def.file)?
+                .id
+                .as_ref()?;
+            (old_id != new_id).then(|| PartTableIdChange {
+                name: def.name.clone(),
+                file: def.file.clone(),
+                old_id: old_id.clone(),
+                new_id: new_id.clone(),
+            })
+        })
+        .collect()
+}
+
+/// List views whose ID changed between `old` and `new`, matching views by name.
+/// Views without an ID in either version are not reported.
+pub fn view_id_changes(old: &LibraryManifest, new: &LibraryManifest) -> Vec<ViewIdChange> {
+    new.views
+        .iter()
+        .filter_map(|view| {
+            let new_id = view.id.as_ref()?;
+            let old_id = old.views.iter().find(|v| v.name == view.name)?.id.as_ref()?;
+            (old_id != new_id).then(|| ViewIdChange {
+                name: view.name.clone(),
+                old_id: old_id.clone(),
+                new_id: new_id.clone(),
+            })
+        })
+        .collect()
 }
 
 /// Load library.yaml from the given library root directory.
@@ -37,6 +281,18 @@ pub fn load_library_manifest(library_root: &Path) -> Result<LibraryManifest, Lib
     Ok(manifest)
 }
 
+/// Load library.yaml, ensuring every part table and view has a unique `id`.
+/// Missing or duplicate IDs are auto-assigned and written back to disk.
+pub fn load_library_manifest_with_ids(library_root: &Path) -> Result<LibraryManifest, LibraryError> {
+    let mut manifest = load_library_manifest(library_root)?;
+    let tables_changed = assign_part_table_ids(&mut manifest);
+    let views_changed = assign_view_ids(&mut manifest);
+    if tables_changed || views_changed {
+        save_library_manifest(library_root, &manifest)?;
+    }
+    Ok(manifest)
+}
+
 /// Save a library manifest (library.yaml) to the given library root directory.
 pub fn save_library_manifest(
     library_root: &Path,
@@ -79,6 +335,32 @@ tables:
         assert_eq!(manifest.part_tables[0].file, "data/resistors.csv");
         assert_eq!(manifest.part_tables[0].template, "resistor");
         assert_eq!(manifest.part_tables[0].name, "Resistors");
+        assert!(manifest.part_tables[0].description.is_none());
+    }
+
+    #[test]
+    fn test_load_part_table_description_and_notes() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("library.yaml"),
+            r#"name: lib
+templates_path: templates
+part_tables:
+  - id: 1
+    file: resistors.csv
+    template: resistor
+    name: Resistors
+    description: Thick film chip resistors
+    notes: |
+      Prefer Yageo RC series.
+"#,
+        )
+        .unwrap();
+
+        let manifest = load_library_manifest(tmp.path()).unwrap();
+        let def = &manifest.part_tables[0];
+        assert_eq!(def.description.as_deref(), Some("Thick film chip resistors"));
+        assert_eq!(def.notes.as_deref(), Some("Prefer Yageo RC series.\n"));
     }
 
     #[test]
@@ -101,4 +383,170 @@ tables:
         assert_eq!(manifest.part_tables.len(), 1);
         assert_eq!(manifest.part_tables[0].template, "resistor");
     }
+
+    fn table(id: Option<&str>, file: &str) -> PartTableDef {
+        PartTableDef {
+            id: id.map(|s| s.to_string()),
+            file: file.to_string(),
+            template: "t".to_string(),
+            name: file.to_string(),
+            description: None,
+            notes: None,
+            id_prefix: None,
+        }
+    }
+
+    fn view(id: Option<&str>) -> ViewDef {
+        ViewDef {
+            id: id.map(|s| s.to_string()),
+            name: "v".to_string(),
+            description: None,
+            notes: None,
+            part_tables: Vec::new(),
+            filter: "package == 0402".to_string(),
+        }
+    }
+
+    fn manifest_with(part_tables: Vec<PartTableDef>) -> LibraryManifest {
+        LibraryManifest {
+            name: "lib".to_string(),
+            description: None,
+            templates_path: "templates".to_string(),
+            part_tables,
+            views: Vec::new(),
+            ids: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_numeric_id_is_accepted() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("library.yaml"),
+            "name: lib\ntemplates_path: t\npart_tables:\n  - id: 7\n    file: a.csv\n    template: a\n    name: A\n",
+        )
+        .unwrap();
+
+        let manifest = load_library_manifest(tmp.path()).unwrap();
+        assert_eq!(manifest.part_tables[0].id.as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn test_assign_ids_uses_position_for_legacy_libraries() {
+        let mut manifest = manifest_with(vec![table(None, "a.csv"), table(None, "b.csv")]);
+        assert!(assign_part_table_ids(&mut manifest));
+        assert_eq!(manifest.part_tables[0].id.as_deref(), Some("1"));
+        assert_eq!(manifest.part_tables[1].id.as_deref(), Some("2"));
+        assert!(!assign_part_table_ids(&mut manifest));
+    }
+
+    #[test]
+    fn test_assign_ids_avoids_existing_and_duplicates() {
+        let mut manifest = manifest_with(vec![
+            table(None, "new.csv"),
+            table(Some("1"), "a.csv"),
+            table(Some("1"), "b.csv"),
+        ]);
+        assert!(assign_part_table_ids(&mut manifest));
+        assert_eq!(manifest.part_tables[0].id.as_deref(), Some("2"));
+        assert_eq!(manifest.part_tables[1].id.as_deref(), Some("1"));
+        assert_eq!(manifest.part_tables[2].id.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn test_load_with_ids_writes_back() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("library.yaml"),
+            "name: lib\ntemplates_path: t\npart_tables:\n  - file: a.csv\n    template: a\n    name: A\n",
+        )
+        .unwrap();
+
+        let manifest = load_library_manifest_with_ids(tmp.path()).unwrap();
+        assert_eq!(manifest.part_tables[0].id.as_deref(), Some("1"));
+
+        let reloaded = load_library_manifest(tmp.path()).unwrap();
+        assert_eq!(reloaded.part_tables[0].id.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_part_table_id_changes() {
+        let old = manifest_with(vec![table(Some("1"), "a.csv"), table(None, "b.csv")]);
+        let new = manifest_with(vec![
+            table(Some("3"), "c.csv"),
+            table(Some("2"), "a.csv"),
+            table(Some("4"), "b.csv"),
+        ]);
+        let changes = part_table_id_changes(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].file, "a.csv");
+        assert_eq!(changes[0].old_id, "1");
+        assert_eq!(changes[0].new_id, "2");
+    }
+
+    #[test]
+    fn test_assign_view_ids_avoids_part_table_ids() {
+        let mut manifest = manifest_with(vec![table(Some("1"), "a.csv"), table(Some("2"), "b.csv")]);
+        manifest.views = vec![view(Some("2")), view(None), view(Some("10"))];
+        assert!(assign_view_ids(&mut manifest));
+        assert_eq!(manifest.views[0].id.as_deref(), Some("3"));
+        assert_eq!(manifest.views[1].id.as_deref(), Some("4"));
+        assert_eq!(manifest.views[2].id.as_deref(), Some("10"));
+        assert!(!assign_view_ids(&mut manifest));
+        assert_eq!(manifest.next_part_table_id(), "11");
+    }
+
+    #[test]
+    fn test_new_part_table_does_not_take_view_id() {
+        let mut manifest = manifest_with(vec![table(Some("1"), "a.csv"), table(None, "b.csv")]);
+        manifest.views = vec![view(Some("2")), view(Some("3"))];
+        assert!(assign_part_table_ids(&mut manifest));
+        assert_eq!(manifest.part_tables[1].id.as_deref(), Some("4"));
+        // The views keep the IDs KiCad has cached
+        assert!(!assign_view_ids(&mut manifest));
+        assert_eq!(manifest.views[0].id.as_deref(), Some("2"));
+        assert_eq!(manifest.views[1].id.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn test_view_id_changes() {
+        let mut old = manifest_with(Vec::new());
+        old.views = vec![view(Some("2")), view(None)];
+        old.views[1].name = "other".to_string();
+        let mut new = old.clone();
+        new.views[0].id = Some("5".to_string());
+        new.views[1].id = Some("6".to_string());
+
+        let changes = view_id_changes(&old, &new);
+        assert_eq!(
+            changes,
+            vec![ViewIdChange {
+                name: "v".to_string(),
+                old_id: "2".to_string(),
+                new_id: "5".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_adding_table_to_library_without_ids_keeps_existing_ids() {
+        let mut manifest = manifest_with(vec![table(None, "a.csv"), table(None, "b.csv")]);
+        manifest.views = vec![view(None)];
+
+        let id = manifest.next_part_table_id();
+        assert_eq!(id, "4");
+        manifest.part_tables.push(table(Some(&id), "c.csv"));
+
+        assign_part_table_ids(&mut manifest);
+        assign_view_ids(&mut manifest);
+        let ids: Vec<_> = manifest.part_tables.iter().map(|t| t.id.as_deref().unwrap()).collect();
+        assert_eq!(ids, ["1", "2", "4"]);
+        assert_eq!(manifest.views[0].id.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn test_next_part_table_id() {
+        let manifest = manifest_with(vec![table(Some("4"), "a.csv"), table(Some("x"), "b.csv")]);
+        assert_eq!(manifest.next_part_table_id(), "5");
+    }
 }
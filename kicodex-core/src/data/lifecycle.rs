@@ -0,0 +1,108 @@
+use std::fmt;
+
+use crate::data::csv_loader::CsvRow;
+
+/// CSV column holding a part's lifecycle status.
+pub const LIFECYCLE_COLUMN: &str = "lifecycle";
+
+/// CSV column pointing a non-active part at the id of its replacement.
+pub const REPLACED_BY_COLUMN: &str = "replaced_by";
+
+/// Lifecycle status of a part.
+///
+/// Only active parts are listed in their category; the others still resolve
+/// by id so schematics that already use them keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lifecycle {
+    Active,
+    /// Not recommended for new designs.
+    Nrnd,
+    Obsolete,
+    Draft,
+}
+
+impl Lifecycle {
+    /// Parse a lifecycle value (case-insensitive). An empty value is active.
+    pub fn parse(value: &str) -> Option<Lifecycle> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "active" => Some(Lifecycle::Active),
+            "nrnd" => Some(Lifecycle::Nrnd),
+            "obsolete" => Some(Lifecycle::Obsolete),
+            "draft" => Some(Lifecycle::Draft),
+            _ => None,
+        }
+    }
+
+    /// Lifecycle of a row. A missing or unrecognized value counts as active,
+    /// so a typo never hides a part (`kicodex validate` reports it instead).
+    pub fn of_row(row: &CsvRow) -> Lifecycle {
+        row.get(LIFECYCLE_COLUMN)
+            .and_then(|v| Lifecycle::parse(v))
+            .unwrap_or(Lifecycle::Active)
+    }
+
+    pub fn is_active(self) -> bool {
+        self == Lifecycle::Active
+    }
+}
+
+impl fmt::Display for Lifecycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Lifecycle::Active => "active",
+            Lifecycle::Nrnd => "NRND",
+            Lifecycle::Obsolete => "obsolete",
+            Lifecycle::Draft => "draft",
+        };
+        f.write_str(s)
+    }
+}
+
+/// The replacement part id of a row, if one is set.
+pub fn replacement(row: &CsvRow) -> Option<&str> {
+    row.get(REPLACED_BY_COLUMN)
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(pairs: &[(&str, &str)]) -> CsvRow {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_lifecycle() {
+        assert_eq!(Lifecycle::parse(""), Some(Lifecycle::Active));
+        assert_eq!(Lifecycle::parse("Active"), Some(Lifecycle::Active));
+        assert_eq!(Lifecycle::parse("NRND"), Some(Lifecycle::Nrnd));
+        assert_eq!(Lifecycle::parse(" obsolete "), Some(Lifecycle::Obsolete));
+        assert_eq!(Lifecycle::parse("draft"), Some(Lifecycle::Draft));
+        assert_eq!(Lifecycle::parse("retired"), None);
+    }
+
+    #[test]
+    fn test_lifecycle_of_row() {
+        assert_eq!(Lifecycle::of_row(&row(&[("id", "1")])), Lifecycle::Active);
+        assert_eq!(
+            Lifecycle::of_row(&row(&[("id", "1"), ("lifecycle", "obsolete")])),
+            Lifecycle::Obsolete
+        );
+        assert_eq!(
+            Lifecycle::of_row(&row(&[("id", "1"), ("lifecycle", "typo")])),
+            Lifecycle::Active
+        );
+    }
+
+    #[test]
+    fn test_replacement() {
+        assert_eq!(replacement(&row(&[("replaced_by", "")])), None);
+        assert_eq!(replacement(&row(&[("replaced_by", " 42 ")])), Some("42"));
+        assert_eq!(replacement(&row(&[("id", "1")])), None);
+    }
+}
@@ -1,5 +1,9 @@
+pub mod computed;
 pub mod csv_loader;
+pub mod filter;
+pub mod ids;
 pub mod kicad_libs;
 pub mod library;
+pub mod lifecycle;
 pub mod project;
 pub mod schema;
@@ -13,14 +13,49 @@ pub enum ProjectError {
 /// Top-level structure of kicodex.yaml in a KiCad project.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ProjectConfig {
+    /// Serve all libraries under one token as a single KiCad library.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregate: Option<AggregateConfig>,
     pub libraries: Vec<LibraryRef>,
 }
 
+/// Settings for serving a project's libraries as one merged KiCad library.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AggregateConfig {
+    /// Name of the merged KiCad library and its `.kicad_httplib` file.
+    pub name: String,
+}
+
 /// A reference to a library directory within the project.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct LibraryRef {
     pub name: String,
     pub path: String,
+    /// Which part tables and rows of the library this project may use.
+    #[serde(flatten)]
+    pub filter: PartFilter,
+}
+
+/// Per-project rules limiting which parts of a library are served.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PartFilter {
+    /// Part tables to serve, by name or template. Empty means all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Part tables never to serve, by name or template.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Row filter expression (see `filter::Filter`); only matching parts are served.
+    #[serde(default, rename = "where", skip_serializing_if = "Option::is_none")]
+    pub row_filter: Option<String>,
+}
+
+impl PartFilter {
+    /// Whether a part table, identified by name and template, is served.
+    pub fn includes_table(&self, name: &str, template: &str) -> bool {
+        let listed = |list: &[String]| list.iter().any(|t| t == name || t == template);
+        (self.include.is_empty() || listed(&self.include)) && !listed(&self.exclude)
+    }
 }
 
 /// Load kicodex.yaml from the given project directory.
@@ -82,6 +117,48 @@ mod tests {
         assert_eq!(config.libraries[1].name, "connectors");
     }
 
+    #[test]
+    fn test_load_project_config_aggregate() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("kicodex.yaml"),
+            r#"aggregate:
+  name: project-parts
+libraries:
+  - name: components
+    path: libs/components
+"#,
+        )
+        .unwrap();
+
+        let config = load_project_config(tmp.path()).unwrap();
+        assert_eq!(config.aggregate.unwrap().name, "project-parts");
+    }
+
+    #[test]
+    fn test_load_project_config_part_filter() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("kicodex.yaml"),
+            r#"libraries:
+  - name: components
+    path: libs/components
+    include: [Resistors, capacitor]
+    exclude: [Capacitors]
+    where: "status == approved"
+"#,
+        )
+        .unwrap();
+
+        let config = load_project_config(tmp.path()).unwrap();
+        let filter = &config.libraries[0].filter;
+        assert_eq!(filter.row_filter.as_deref(), Some("status == approved"));
+        assert!(filter.includes_table("Resistors", "resistor"));
+        assert!(!filter.includes_table("Capacitors", "capacitor"));
+        assert!(!filter.includes_table("Inductors", "inductor"));
+        assert!(PartFilter::default().includes_table("Inductors", "inductor"));
+    }
+
     #[test]
     fn test_load_project_config_missing_file() {
         let tmp = TempDir::new().unwrap();
@@ -15,10 +15,21 @@ pub enum SchemaError {
     MissingParent(String),
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct RawSchema {
     #[serde(alias = "inherits")]
     pub based_on: Option<String>,
+    /// Category description used when the part table in library.yaml has none.
+    /// Not inherited from parent templates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Part name template, e.g. `"{value} {tolerance} {package}"`.
+    /// Inherited from the parent template when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_format: Option<String>,
+    /// Part summary description template. Inherited like `name_format`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_format: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub exclude_from_bom: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -28,7 +39,7 @@ pub struct RawSchema {
     pub fields: IndexMap<String, FieldDef>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Hash, Deserialize, Serialize)]
 pub struct FieldDef {
     pub display_name: String,
     #[serde(default)]
@@ -41,17 +52,42 @@ pub struct FieldDef {
     pub description: Option<String>,
     #[serde(rename = "type", default)]
     pub field_type: Option<String>,
+    /// Formula computing this field from other columns of the same row,
+    /// using the `{field}` syntax of `name_format`. Computed at load time
+    /// and never written to CSV.
+    #[serde(default, alias = "computed", skip_serializing_if = "Option::is_none")]
+    pub expr: Option<String>,
+    /// Set to `false` for internal-only columns (cost, purchasing notes, ...)
+    /// that stay editable and validated but are never served to KiCad.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export: Option<bool>,
 }
 
 /// A fully resolved schema with inherited fields merged in.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ResolvedSchema {
+    pub description: Option<String>,
+    pub name_format: Option<String>,
+    pub description_format: Option<String>,
     pub exclude_from_bom: bool,
     pub exclude_from_board: bool,
     pub exclude_from_sim: bool,
     pub fields: IndexMap<String, FieldDef>,
 }
 
+impl ResolvedSchema {
+    /// Whether a CSV column should be served to KiCad. Columns whose name
+    /// starts with `_` and fields marked `export: false` are internal-only.
+    pub fn is_exported(&self, column: &str) -> bool {
+        !column.starts_with('_')
+            && self
+                .fields
+                .get(column)
+                .and_then(|f| f.export)
+                .unwrap_or(true)
+    }
+}
+
 /// Load and resolve a named schema from a schemas directory.
 /// The `schema_name` should not include the `.yaml` extension.
 pub fn load_schema(schemas_dir: &Path, schema_name: &str) -> Result<ResolvedSchema, SchemaError> {
@@ -78,6 +114,8 @@ pub fn load_schema(schemas_dir: &Path, schema_name: &str) -> Result<ResolvedSche
     let mut exclude_from_bom = raw.exclude_from_bom.unwrap_or(false);
     let mut exclude_from_board = raw.exclude_from_board.unwrap_or(false);
     let mut exclude_from_sim = raw.exclude_from_sim.unwrap_or(false);
+    let mut name_format = raw.name_format.clone();
+    let mut description_format = raw.description_format.clone();
 
     // If this schema inherits from a parent, start with parent values
     // and let child override only when explicitly set (Some).
@@ -94,12 +132,17 @@ pub fn load_schema(schemas_dir: &Path, schema_name: &str) -> Result<ResolvedSche
         exclude_from_bom = raw.exclude_from_bom.unwrap_or(parent.exclude_from_bom);
         exclude_from_board = raw.exclude_from_board.unwrap_or(parent.exclude_from_board);
         exclude_from_sim = raw.exclude_from_sim.unwrap_or(parent.exclude_from_sim);
+        name_format = name_format.or(parent.name_format);
+        description_format = description_format.or(parent.description_format);
     }
 
     // Type-specific fields override/extend base fields
     fields.extend(raw.fields);
 
     Ok(ResolvedSchema {
+        description: raw.description,
+        name_format,
+        description_format,
         exclude_from_bom,
         exclude_from_board,
         exclude_from_sim,
@@ -107,8 +150,31 @@ pub fn load_schema(schemas_dir: &Path, schema_name: &str) -> Result<ResolvedSche
     })
 }
 
+/// Names of the template files a schema is resolved from: `schema_name`
+/// itself, then its parents up the `based_on` chain (ending in `_base` when
+/// the chain reaches it). A template that can't be read ends the chain.
+pub fn template_chain(schemas_dir: &Path, schema_name: &str) -> Vec<String> {
+    let mut chain = vec![schema_name.to_string()];
+    let mut current = schema_name.to_string();
+    while let Some(parent) = std::fs::read_to_string(schemas_dir.join(format!("{current}.yaml")))
+        .ok()
+        .and_then(|content| serde_yml::from_str::<RawSchema>(&content).ok())
+        .and_then(|raw| raw.based_on)
+    {
+        if chain.contains(&parent) {
+            break;
+        }
+        chain.push(parent.clone());
+        current = parent;
+    }
+    chain
+}
+
 fn resolve_raw_schema(raw: RawSchema) -> ResolvedSchema {
     ResolvedSchema {
+        description: raw.description,
+        name_format: raw.name_format,
+        description_format: raw.description_format,
         exclude_from_bom: raw.exclude_from_bom.unwrap_or(false),
         exclude_from_board: raw.exclude_from_board.unwrap_or(false),
         exclude_from_sim: raw.exclude_from_sim.unwrap_or(false),
@@ -116,6 +182,106 @@ fn resolve_raw_schema(raw: RawSchema) -> ResolvedSchema {
     }
 }
 
+/// A piece of a name/description format string.
+#[derive(Debug, PartialEq)]
+enum FormatSegment<'a> {
+    /// Literal text, with `{{`/`}}` unescaped.
+    Text(String),
+    /// A `{field}` placeholder, trimmed.
+    Placeholder(&'a str),
+}
+
+/// Split a format string into literal text and placeholders. `{{` and `}}`
+/// are literal braces. A `{` with no `}` before the next `{` (or the end) is
+/// unclosed and kept as literal text, like a lone `}`. Also returns the byte
+/// offsets of those unbalanced braces.
+fn parse_format(format: &str) -> (Vec<FormatSegment<'_>>, Vec<usize>) {
+    let mut segments = Vec::new();
+    let mut unbalanced = Vec::new();
+    let mut text = String::new();
+    let mut rest = format;
+    while let Some(c) = rest.chars().next() {
+        let offset = format.len() - rest.len();
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            text.push(c);
+            rest = &rest[2..];
+            continue;
+        }
+        match c {
+            '{' => match rest[1..].find(['{', '}']) {
+                Some(end) if rest[1..][end..].starts_with('}') => {
+                    if !text.is_empty() {
+                        segments.push(FormatSegment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(FormatSegment::Placeholder(rest[1..1 + end].trim()));
+                    rest = &rest[end + 2..];
+                    continue;
+                }
+                _ => unbalanced.push(offset),
+            },
+            '}' => unbalanced.push(offset),
+            _ => {}
+        }
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if !text.is_empty() {
+        segments.push(FormatSegment::Text(text));
+    }
+    (segments, unbalanced)
+}
+
+/// List the `{field}` placeholders in a name/description format string.
+/// `{{` and `}}` are literal braces.
+pub fn format_placeholders(format: &str) -> Vec<String> {
+    parse_format(format)
+        .0
+        .into_iter()
+        .filter_map(|segment| match segment {
+            FormatSegment::Placeholder(name) => Some(name.to_string()),
+            FormatSegment::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Describe the first unclosed `{` or unmatched `}` in a format string, if any.
+pub fn format_brace_error(format: &str) -> Option<String> {
+    let offset = *parse_format(format).1.first()?;
+    let brace = &format[offset..offset + 1];
+    let kind = if brace == "{" { "unclosed" } else { "unmatched" };
+    Some(format!(
+        "{} '{}' at position {} (use '{}{}' for a literal brace)",
+        kind,
+        brace,
+        format[..offset].chars().count() + 1,
+        brace,
+        brace
+    ))
+}
+
+/// Render a format string against a row. Unknown or empty placeholders render
+/// as empty, and the resulting runs of whitespace are collapsed. Unbalanced
+/// braces are rendered literally.
+pub fn render_format(format: &str, row: &IndexMap<String, String>) -> String {
+    render_format_with(format, |name| row.get(name).map(String::as_str))
+}
+
+/// `render_format`, looking placeholder values up with `value_of`.
+pub fn render_format_with<'a>(format: &str, value_of: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut out = String::new();
+    for segment in parse_format(format).0 {
+        match segment {
+            FormatSegment::Text(text) => out.push_str(&text),
+            FormatSegment::Placeholder(name) => {
+                if let Some(value) = value_of(name) {
+                    out.push_str(value);
+                }
+            }
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 /// Write a raw schema to a YAML file in the schemas directory.
 pub fn write_schema(
     schemas_dir: &Path,
@@ -145,12 +311,12 @@ pub fn write_template(schemas_dir: &Path, name: &str, schema: &RawSchema) -> Res
 /// Returns the default RawSchema used when scaffolding a new part table.
 pub fn default_schema() -> RawSchema {
     let mut fields = IndexMap::new();
-    fields.insert("value".to_string(),       FieldDef { display_name: "Value".to_string(),       required: true,  visible: true,  description: None, field_type: None });
-    fields.insert("description".to_string(), FieldDef { display_name: "Description".to_string(), required: true,  visible: false, description: None, field_type: None });
-    fields.insert("footprint".to_string(),   FieldDef { display_name: "Footprint".to_string(),   required: true,  visible: false, description: None, field_type: Some("kicad_footprint".to_string()) });
-    fields.insert("symbol".to_string(),      FieldDef { display_name: "Symbol".to_string(),      required: true,  visible: false, description: None, field_type: Some("kicad_symbol".to_string()) });
-    fields.insert("datasheet".to_string(),   FieldDef { display_name: "Datasheet".to_string(),   required: false, visible: false, description: None, field_type: Some("url".to_string()) });
-    RawSchema { based_on: None, exclude_from_bom: None, exclude_from_board: None, exclude_from_sim: None, fields }
+    fields.insert("value".to_string(),       FieldDef { display_name: "Value".to_string(),       required: true,  visible: true,  description: None, field_type: None, expr: None, export: None });
+    fields.insert("description".to_string(), FieldDef { display_name: "Description".to_string(), required: true,  visible: false, description: None, field_type: None, expr: None, export: None });
+    fields.insert("footprint".to_string(),   FieldDef { display_name: "Footprint".to_string(),   required: true,  visible: false, description: None, field_type: Some("kicad_footprint".to_string()), expr: None, export: None });
+    fields.insert("symbol".to_string(),      FieldDef { display_name: "Symbol".to_string(),      required: true,  visible: false, description: None, field_type: Some("kicad_symbol".to_string()), expr: None, export: None });
+    fields.insert("datasheet".to_string(),   FieldDef { display_name: "Datasheet".to_string(),   required: false, visible: false, description: None, field_type: Some("url".to_string()), expr: None, export: None });
+    RawSchema { fields, ..Default::default() }
 }
 
 /// Returns the default CSV header row for a new part table (matches default_schema field order).
@@ -249,6 +415,25 @@ fields:
         assert_eq!(keys[7], "resistance");
     }
 
+    #[test]
+    fn test_template_chain() {
+        let tmp = TempDir::new().unwrap();
+        let schemas_dir = tmp.path().join("schemas");
+        write_test_schemas(&schemas_dir);
+        fs::write(
+            schemas_dir.join("smd_resistor.yaml"),
+            "based_on: resistor\nfields: {}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            template_chain(&schemas_dir, "smd_resistor"),
+            vec!["smd_resistor", "resistor", "_base"]
+        );
+        assert_eq!(template_chain(&schemas_dir, "_base"), vec!["_base"]);
+        assert_eq!(template_chain(&schemas_dir, "missing"), vec!["missing"]);
+    }
+
     #[test]
     fn test_load_inherited_template_old_key() {
         let tmp = TempDir::new().unwrap();
@@ -322,4 +507,110 @@ fields:
         let schema = load_schema(&schemas_dir, "child").unwrap();
         assert!(schema.exclude_from_bom, "child should inherit parent's true when field is omitted");
     }
+
+    #[test]
+    fn test_formats_are_inherited_and_overridable() {
+        let tmp = TempDir::new().unwrap();
+        let schemas_dir = tmp.path().join("schemas");
+        fs::create_dir_all(&schemas_dir).unwrap();
+
+        fs::write(
+            schemas_dir.join("_base.yaml"),
+            "name_format: \"{mpn}\"\ndescription_format: \"{manufacturer} {mpn}\"\nfields: {}\n",
+        ).unwrap();
+        fs::write(
+            schemas_dir.join("resistor.yaml"),
+            "based_on: _base\nname_format: \"{value} {tolerance} {package}\"\nfields: {}\n",
+        ).unwrap();
+
+        let schema = load_schema(&schemas_dir, "resistor").unwrap();
+        assert_eq!(schema.name_format.as_deref(), Some("{value} {tolerance} {package}"));
+        assert_eq!(schema.description_format.as_deref(), Some("{manufacturer} {mpn}"));
+    }
+
+    #[test]
+    fn test_render_format() {
+        let row: IndexMap<String, String> = [
+            ("value", "10K"),
+            ("tolerance", ""),
+            ("package", "0603"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        assert_eq!(render_format("{value} {tolerance} {package}", &row), "10K 0603");
+        assert_eq!(render_format("R {{{value}}}", &row), "R {10K}");
+        assert_eq!(render_format("{ missing }", &row), "");
+        assert_eq!(
+            format_placeholders("{value} {{literal}} { package }"),
+            vec!["value".to_string(), "package".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_braces_render_literally() {
+        let row: IndexMap<String, String> = [("value", "10K"), ("package", "0603")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        assert_eq!(render_format("{value {package}", &row), "{value 0603");
+        assert_eq!(render_format("{value} {package", &row), "10K {package");
+        assert_eq!(render_format("{value}} x", &row), "10K} x");
+        assert_eq!(format_placeholders("{value {package}"), vec!["package".to_string()]);
+
+        assert_eq!(format_brace_error("{value} {{literal}}"), None);
+        assert_eq!(
+            format_brace_error("{value {package}").as_deref(),
+            Some("unclosed '{' at position 1 (use '{{' for a literal brace)")
+        );
+        assert_eq!(
+            format_brace_error("R} {value}").as_deref(),
+            Some("unmatched '}' at position 2 (use '}}' for a literal brace)")
+        );
+    }
+
+    #[test]
+    fn test_internal_columns_are_not_exported() {
+        let tmp = TempDir::new().unwrap();
+        let schemas_dir = tmp.path().join("schemas");
+        fs::create_dir_all(&schemas_dir).unwrap();
+
+        fs::write(
+            schemas_dir.join("parts.yaml"),
+            "fields:\n  cost:\n    display_name: Cost\n    export: false\n  value:\n    display_name: Value\n",
+        ).unwrap();
+
+        let schema = load_schema(&schemas_dir, "parts").unwrap();
+        assert!(schema.is_exported("value"));
+        assert!(schema.is_exported("untemplated"));
+        assert!(!schema.is_exported("cost"));
+        assert!(!schema.is_exported("_review_notes"));
+    }
+
+    #[test]
+    fn test_template_description_is_not_inherited() {
+        let tmp = TempDir::new().unwrap();
+        let schemas_dir = tmp.path().join("schemas");
+        fs::create_dir_all(&schemas_dir).unwrap();
+
+        fs::write(
+            schemas_dir.join("_base.yaml"),
+            "description: Base parts\nfields:\n  mpn:\n    display_name: MPN\n",
+        ).unwrap();
+        fs::write(
+            schemas_dir.join("resistor.yaml"),
+            "based_on: _base\ndescription: Thick film resistors\nfields: {}\n",
+        ).unwrap();
+        fs::write(
+            schemas_dir.join("plain.yaml"),
+            "based_on: _base\nfields: {}\n",
+        ).unwrap();
+
+        let resistor = load_schema(&schemas_dir, "resistor").unwrap();
+        assert_eq!(resistor.description.as_deref(), Some("Thick film resistors"));
+        let plain = load_schema(&schemas_dir, "plain").unwrap();
+        assert!(plain.description.is_none());
+    }
 }
@@ -1,12 +1,20 @@
+use std::collections::HashSet;
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::data::project;
-use crate::registry::{PersistedRegistry, ProjectEntry, ProjectRegistry};
+use crate::data::{library, project};
+use crate::registry::{
+    AggregateMember, PersistedRegistry, ProjectEntry, ProjectRegistry, RetiredToken,
+};
 use crate::server;
 
 /// Generate the expected `.kicad_httplib` content for a library.
-fn expected_httplib_content(name: &str, description: &str, token: &str, port: u16) -> String {
+fn expected_httplib_content(
+    name: &str,
+    description: &str,
+    token: &str,
+    root_url: &str,
+) -> String {
     format!(
         r#"{{
     "meta": {{
@@ -17,7 +25,7 @@ fn expected_httplib_content(name: &str, description: &str, token: &str, port: u1
     "source": {{
         "type": "REST_API",
         "api_version": "v1",
-        "root_url": "http://127.0.0.1:{port}",
+        "root_url": "{root_url}",
         "token": "{token}"
     }}
 }}"#
@@ -33,7 +41,8 @@ fn resolve_description(name: &str, description: Option<&str>) -> String {
 
 /// Ensure all `.kicad_httplib` files for project-attached entries are up to date.
 /// Called at app startup to heal any stale files.
-pub fn ensure_all_httplib_files(persisted: &PersistedRegistry, port: u16) {
+pub fn ensure_all_httplib_files(persisted: &PersistedRegistry, root_url: &str) {
+    let mut seen = HashSet::new();
     for entry in &persisted.projects {
         let project_path = match entry.project_path.as_deref() {
             Some(pp) => std::path::Path::new(pp),
@@ -42,32 +51,40 @@ pub fn ensure_all_httplib_files(persisted: &PersistedRegistry, port: u16) {
         if !project_path.is_dir() {
             continue; // stale entry, cleanup will handle it
         }
+        // Aggregated entries share one file
+        if !seen.insert((project_path, entry.httplib_name())) {
+            continue;
+        }
+        let description = match entry.group {
+            Some(_) => None,
+            None => entry.description.as_deref(),
+        };
         if let Err(e) = ensure_httplib_file(
             project_path,
-            &entry.name,
-            entry.description.as_deref(),
+            entry.httplib_name(),
+            description,
             &entry.token,
-            port,
+            root_url,
         ) {
-            tracing::warn!("Failed to ensure .kicad_httplib for {}: {}", entry.name, e);
+            tracing::warn!("Failed to ensure .kicad_httplib for {}: {}", entry.httplib_name(), e);
         }
     }
 }
 
-/// Ensure the `.kicad_httplib` file exists and has the correct token/port.
-/// Rewrites the file if it's missing, has a stale token, or wrong port.
+/// Ensure the `.kicad_httplib` file exists and has the correct token/root URL.
+/// Rewrites the file if it's missing, has a stale token, or a different root URL.
 pub fn ensure_httplib_file(
     project_dir: &Path,
     name: &str,
     description: Option<&str>,
     token: &str,
-    port: u16,
+    root_url: &str,
 ) -> Result<(), std::io::Error> {
     let kicodex_dir = project_dir.join(".kicodex");
     std::fs::create_dir_all(&kicodex_dir)?;
     let httplib_path = kicodex_dir.join(format!("{}.kicad_httplib", name));
     let desc = resolve_description(name, description);
-    let expected = expected_httplib_content(name, &desc, token, port);
+    let expected = expected_httplib_content(name, &desc, token, root_url);
 
     // Check if the file already has the right content
     if httplib_path.exists() {
@@ -76,7 +93,7 @@ pub fn ensure_httplib_file(
                 return Ok(());
             }
             tracing::info!(
-                "Updating {} (token or port changed)",
+                "Updating {} (token or root URL changed)",
                 httplib_path.display()
             );
         }
@@ -88,12 +105,91 @@ pub fn ensure_httplib_file(
     Ok(())
 }
 
+/// Remove a `.kicad_httplib` file written for a project, if present.
+pub fn remove_httplib_file(project_dir: &Path, name: &str) -> Result<(), std::io::Error> {
+    let httplib_path = project_dir
+        .join(".kicodex")
+        .join(format!("{}.kicad_httplib", name));
+    if httplib_path.exists() {
+        tracing::info!("Removing {}", httplib_path.display());
+        std::fs::remove_file(&httplib_path)?;
+    }
+    Ok(())
+}
+
+/// Register all libraries of a project under one aggregated token and write
+/// the group's single `.kicad_httplib` file.
+///
+/// The group's existing token is reused. `.kicad_httplib` files of the same
+/// libraries registered individually before are removed, so KiCad doesn't
+/// list them twice. Returns the token and the loaded member libraries, for
+/// the caller to insert into a runtime registry.
+pub fn register_aggregate(
+    project_dir: &Path,
+    aggregate: &project::AggregateConfig,
+    libraries: &[project::LibraryRef],
+    persisted: &mut PersistedRegistry,
+    root_url: &str,
+) -> Result<(String, Vec<AggregateMember>), AutoRegisterError> {
+    let project_path_str = project_dir.to_string_lossy().to_string();
+    let project_entries = || {
+        persisted
+            .projects
+            .iter()
+            .filter(|p| p.project_path.as_deref() == Some(project_path_str.as_str()))
+    };
+
+    // Keep the group's token (and its restrictions) across re-registration
+    let (token, access) = project_entries()
+        .find(|p| p.group.as_deref() == Some(aggregate.name.as_str()))
+        .map(|p| (p.token.clone(), p.access.clone()))
+        .unwrap_or_else(|| (uuid::Uuid::new_v4().to_string(), Default::default()));
+
+    let stale_files: Vec<String> = project_entries()
+        .filter(|p| libraries.iter().any(|l| l.name == p.name))
+        .map(|p| p.httplib_name().to_string())
+        .filter(|name| *name != aggregate.name)
+        .collect();
+
+    let mut members = Vec::new();
+    for lib_ref in libraries {
+        let library_path = project_dir.join(&lib_ref.path);
+        let library_path = library_path
+            .canonicalize()
+            .unwrap_or_else(|_| library_path.clone());
+
+        let library = server::load_library_filtered(&library_path, &lib_ref.filter)?;
+        persisted.upsert(ProjectEntry {
+            token: token.clone(),
+            project_path: Some(project_path_str.clone()),
+            library_path: library_path.to_string_lossy().to_string(),
+            name: lib_ref.name.clone(),
+            description: library.description.clone(),
+            group: Some(aggregate.name.clone()),
+            access: access.clone(),
+        });
+        members.push(AggregateMember {
+            name: lib_ref.name.clone(),
+            path: library_path,
+            filter: lib_ref.filter.clone(),
+            library: Arc::new(library),
+        });
+    }
+
+    for name in stale_files {
+        remove_httplib_file(project_dir, &name)?;
+    }
+    ensure_httplib_file(project_dir, &aggregate.name, None, &token, root_url)?;
+
+    Ok((token, members))
+}
+
 /// Try to auto-register a project directory with KiCodex.
 ///
-/// Checks for `kicodex.yaml` in the project directory. If found and not already
-/// registered, generates tokens, loads the library, registers it, and writes
-/// `.kicad_httplib` files. For already-registered libraries, ensures the
-/// `.kicad_httplib` file exists and has the correct token/port.
+/// Checks for `kicodex.yaml` in the project directory and registers its
+/// libraries with `register_project`. When any library was newly registered,
+/// the project's libraries are (re)loaded into the runtime registry; one
+/// that fails to load is served as a load-error placeholder.
 ///
 /// Returns the number of newly registered libraries (0 if all were already registered
 /// or no kicodex.yaml found).
@@ -101,7 +197,7 @@ pub fn try_auto_register(
     project_dir: &Path,
     persisted: &mut PersistedRegistry,
     registry: &Arc<ProjectRegistry>,
-    port: u16,
+    root_url: &str,
 ) -> Result<usize, AutoRegisterError> {
     let config = match project::load_project_config(project_dir) {
         Ok(c) => c,
@@ -112,76 +208,195 @@ pub fn try_auto_register(
         Err(e) => return Err(AutoRegisterError::Project(e)),
     };
 
-    let project_path_str = project_dir.to_string_lossy().to_string();
-    let mut newly_registered = 0;
-
-    for lib_ref in &config.libraries {
-        // Check if already registered by matching project_path + library name
-        let existing = persisted
+    let newly_registered = register_project(project_dir, &config, persisted, root_url)?;
+    if newly_registered > 0 {
+        tracing::info!(
+            "Auto-registered {} library/libraries of {}",
+            newly_registered,
+            project_dir.display()
+        );
+        let project_path_str = project_dir.to_string_lossy().to_string();
+        let entries: Vec<ProjectEntry> = persisted
             .projects
             .iter()
-            .find(|p| p.project_path.as_deref() == Some(project_path_str.as_str()) && p.name == lib_ref.name);
-
-        if let Some(entry) = existing {
-            // Already registered — just ensure the .kicad_httplib file is correct
-            if let Err(e) = ensure_httplib_file(
-                project_dir,
-                &lib_ref.name,
-                entry.description.as_deref(),
-                &entry.token,
-                port,
-            ) {
-                tracing::warn!(
-                    "Failed to update .kicad_httplib for {}: {}",
-                    lib_ref.name,
-                    e
-                );
-            }
-            continue;
-        }
+            .filter(|p| p.project_path.as_deref() == Some(project_path_str.as_str()))
+            .cloned()
+            .collect();
+        registry.load_entries(&entries);
+    }
+    Ok(newly_registered)
+}
 
+/// Register the libraries listed in a project's `kicodex.yaml` and write
+/// their `.kicad_httplib` files, without loading the libraries.
+///
+/// Registered libraries keep their tokens and only get their files healed.
+/// New libraries of an aggregated project join the group's token. A library
+/// that doesn't load is registered all the same; loading is left to
+/// `ProjectRegistry::load_entries`, which reports the error. Returns the
+/// number of newly registered libraries.
+pub fn register_project(
+    project_dir: &Path,
+    config: &project::ProjectConfig,
+    persisted: &mut PersistedRegistry,
+    root_url: &str,
+) -> Result<usize, AutoRegisterError> {
+    let project_path_str = project_dir.to_string_lossy().to_string();
+    let in_project = |p: &ProjectEntry| p.project_path.as_deref() == Some(project_path_str.as_str());
+    let group = config.aggregate.as_ref().map(|a| a.name.clone());
+
+    let new_libraries: Vec<&project::LibraryRef> = config
+        .libraries
+        .iter()
+        .filter(|lib_ref| {
+            !persisted
+                .projects
+                .iter()
+                .any(|p| in_project(p) && p.name == lib_ref.name && p.group == group)
+        })
+        .collect();
+
+    // Keep the group's token (and its restrictions) when libraries join it
+    let group_token = group.as_ref().map(|group| {
+        persisted
+            .projects
+            .iter()
+            .find(|p| in_project(p) && p.group.as_ref() == Some(group))
+            .map(|p| (p.token.clone(), p.access.clone()))
+            .unwrap_or_else(|| (uuid::Uuid::new_v4().to_string(), Default::default()))
+    });
+
+    // Files the new entries replace: a library's own file when it joins the
+    // group, the old group's file when it is served individually again
+    let stale_files: HashSet<String> = persisted
+        .projects
+        .iter()
+        .filter(|p| in_project(p) && p.group != group)
+        .filter(|p| new_libraries.iter().any(|l| l.name == p.name))
+        .map(|p| p.httplib_name().to_string())
+        .collect();
+
+    for lib_ref in &new_libraries {
         let library_path = project_dir.join(&lib_ref.path);
         let library_path = library_path
             .canonicalize()
             .unwrap_or_else(|_| library_path.clone());
-
-        // Validate that the library can actually be loaded
-        let library = server::load_library(&library_path)?;
+        // Only read for the description; the library is loaded when served
+        let description = library::load_library_manifest(&library_path)
+            .ok()
+            .and_then(|manifest| manifest.description);
+        let (token, access) = group_token
+            .clone()
+            .unwrap_or_else(|| (uuid::Uuid::new_v4().to_string(), Default::default()));
         tracing::info!(
-            "Auto-discovered library '{}' at {}",
-            library.name,
+            "Registering library '{}' at {}",
+            lib_ref.name,
             library_path.display()
         );
-
-        let token = uuid::Uuid::new_v4().to_string();
-
-        let description = library.description.clone();
-
         persisted.upsert(ProjectEntry {
-            token: token.clone(),
+            token,
             project_path: Some(project_path_str.clone()),
             library_path: library_path.to_string_lossy().to_string(),
             name: lib_ref.name.clone(),
-            description: description.clone(),
+            description,
+            group: group.clone(),
+            access,
         });
+    }
 
-        // Register in runtime registry
-        registry.insert(&token, library);
+    for name in stale_files {
+        remove_httplib_file(project_dir, &name)?;
+    }
 
-        // Write .kicad_httplib file
-        ensure_httplib_file(
+    let mut written = HashSet::new();
+    for entry in persisted.projects.iter().filter(|p| in_project(p) && p.group == group) {
+        if !config.libraries.iter().any(|l| l.name == entry.name)
+            || !written.insert(entry.httplib_name())
+        {
+            continue;
+        }
+        let description = match entry.group {
+            Some(_) => None,
+            None => entry.description.as_deref(),
+        };
+        let result = ensure_httplib_file(
             project_dir,
-            &lib_ref.name,
-            description.as_deref(),
-            &token,
-            port,
-        )
-        .map_err(AutoRegisterError::Io)?;
+            entry.httplib_name(),
+            description,
+            &entry.token,
+            root_url,
+        );
+        match result {
+            // A new library's file has to be written for KiCad to find it
+            Err(e) if new_libraries.iter().any(|l| l.name == entry.name) => return Err(e.into()),
+            Err(e) => tracing::warn!(
+                "Failed to update .kicad_httplib for {}: {}",
+                entry.httplib_name(),
+                e
+            ),
+            Ok(()) => {}
+        }
+    }
 
-        newly_registered += 1;
+    Ok(new_libraries.len())
+}
+
+/// Replace a token with a freshly generated one and rewrite the
+/// `.kicad_httplib` file that holds it.
+///
+/// The old token keeps working for `grace` (requests using it are logged as
+/// deprecated); a zero grace revokes it immediately. A token retired by an
+/// earlier rotation is revoked either way. Returns the new token.
+pub fn rotate_token(
+    persisted: &mut PersistedRegistry,
+    token: &str,
+    grace: std::time::Duration,
+    root_url: &str,
+) -> Result<String, AutoRegisterError> {
+    if persisted.find_by_token(token).is_none() {
+        return Err(AutoRegisterError::Registry(format!(
+            "no library registered with token {}...",
+            &token[..token.len().min(8)]
+        )));
     }
 
-    Ok(newly_registered)
+    let new_token = uuid::Uuid::new_v4().to_string();
+    let previous_token = (!grace.is_zero()).then(|| RetiredToken {
+        token: token.to_string(),
+        valid_until: chrono::Duration::from_std(grace)
+            .ok()
+            .and_then(|grace| chrono::Utc::now().checked_add_signed(grace))
+            .unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC),
+    });
+
+    let mut httplib = None;
+    for entry in persisted.projects.iter_mut().filter(|e| e.token == token) {
+        entry.token = new_token.clone();
+        entry.access.previous_token = previous_token.clone();
+        if let (None, Some(project_path)) = (&httplib, &entry.project_path) {
+            let description = match entry.group {
+                Some(_) => None,
+                None => entry.description.clone(),
+            };
+            httplib = Some((
+                project_path.clone(),
+                entry.httplib_name().to_string(),
+                description,
+            ));
+        }
+    }
+
+    // Standalone libraries have no .kicad_httplib file
+    if let Some((project_path, name, description)) = httplib {
+        ensure_httplib_file(
+            Path::new(&project_path),
+            &name,
+            description.as_deref(),
+            &new_token,
+            root_url,
+        )?;
+    }
+    Ok(new_token)
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -259,7 +474,7 @@ tables:
         let mut persisted = PersistedRegistry::default();
         let registry = Arc::new(ProjectRegistry::new());
 
-        let count = try_auto_register(project_dir, &mut persisted, &registry, 18734).unwrap();
+        let count = try_auto_register(project_dir, &mut persisted, &registry, "http://127.0.0.1:18734").unwrap();
 
         assert_eq!(count, 1);
         assert_eq!(persisted.projects.len(), 1);
@@ -271,6 +486,73 @@ tables:
         assert!(httplib.exists());
     }
 
+    #[test]
+    fn test_auto_register_aggregate_writes_single_httplib() {
+        let tmp = TempDir::new().unwrap();
+        let project_dir = tmp.path();
+        for name in ["passives", "connectors"] {
+            let lib_dir = project_dir.join("libs").join(name);
+            fs::create_dir_all(&lib_dir).unwrap();
+            create_minimal_library(&lib_dir);
+        }
+        fs::write(
+            project_dir.join("kicodex.yaml"),
+            "aggregate:\n  name: project-parts\nlibraries:\n  - name: passives\n    path: libs/passives\n  - name: connectors\n    path: libs/connectors\n",
+        )
+        .unwrap();
+
+        let mut persisted = PersistedRegistry::default();
+        let registry = Arc::new(ProjectRegistry::new());
+
+        let count = try_auto_register(project_dir, &mut persisted, &registry, "http://127.0.0.1:18734").unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(persisted.projects.len(), 2);
+        assert_eq!(persisted.projects[0].token, persisted.projects[1].token);
+        assert_eq!(registry.tokens().len(), 1);
+
+        let kicodex_dir = project_dir.join(".kicodex");
+        assert!(kicodex_dir.join("project-parts.kicad_httplib").exists());
+        assert!(!kicodex_dir.join("passives.kicad_httplib").exists());
+
+        let library = registry.get(&persisted.projects[0].token).unwrap();
+        assert_eq!(library.part_tables.len(), 2);
+        assert_eq!(library.part_tables[0].id, "passives:1");
+        assert_eq!(library.part_tables[0].name, "passives / Resistors");
+        assert!(library.find_part("connectors:1").is_some());
+
+        // Registering again is a no-op
+        let count = try_auto_register(project_dir, &mut persisted, &registry, "http://127.0.0.1:18734").unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_auto_register_keeps_going_past_broken_library() {
+        let tmp = TempDir::new().unwrap();
+        let (project_dir, _) = setup_project(&tmp);
+        let broken_dir = project_dir.join("libs").join("broken");
+        fs::create_dir_all(&broken_dir).unwrap();
+        fs::write(broken_dir.join("library.yaml"), "name: [unclosed\n").unwrap();
+        fs::write(
+            project_dir.join("kicodex.yaml"),
+            "libraries:\n  - name: broken\n    path: libs/broken\n  - name: components\n    path: libs/components\n",
+        )
+        .unwrap();
+
+        let mut persisted = PersistedRegistry::default();
+        let registry = Arc::new(ProjectRegistry::new());
+
+        let count = try_auto_register(project_dir, &mut persisted, &registry, "http://127.0.0.1:18734").unwrap();
+        assert_eq!(count, 2);
+        let kicodex_dir = project_dir.join(".kicodex");
+        assert!(kicodex_dir.join("broken.kicad_httplib").exists());
+        assert!(kicodex_dir.join("components.kicad_httplib").exists());
+
+        // The broken library is served as a load-error placeholder
+        let status = registry.status();
+        assert_eq!(status.len(), 2);
+        assert_eq!(status.iter().filter(|s| s.error.is_some()).count(), 1);
+    }
+
     #[test]
     fn test_auto_register_skips_already_registered() {
         let tmp = TempDir::new().unwrap();
@@ -283,10 +565,12 @@ tables:
             library_path: lib_dir.to_string_lossy().to_string(),
             name: "components".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
 
         let registry = Arc::new(ProjectRegistry::new());
-        let count = try_auto_register(project_dir, &mut persisted, &registry, 18734).unwrap();
+        let count = try_auto_register(project_dir, &mut persisted, &registry, "http://127.0.0.1:18734").unwrap();
 
         assert_eq!(count, 0);
     }
@@ -297,7 +581,7 @@ tables:
         let mut persisted = PersistedRegistry::default();
         let registry = Arc::new(ProjectRegistry::new());
 
-        let count = try_auto_register(tmp.path(), &mut persisted, &registry, 18734).unwrap();
+        let count = try_auto_register(tmp.path(), &mut persisted, &registry, "http://127.0.0.1:18734").unwrap();
 
         assert_eq!(count, 0);
     }
@@ -314,6 +598,8 @@ tables:
             library_path: lib_dir.to_string_lossy().to_string(),
             name: "components".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
 
         let registry = Arc::new(ProjectRegistry::new());
@@ -322,7 +608,7 @@ tables:
         let httplib = project_dir.join(".kicodex").join("components.kicad_httplib");
         assert!(!httplib.exists());
 
-        let count = try_auto_register(project_dir, &mut persisted, &registry, 18734).unwrap();
+        let count = try_auto_register(project_dir, &mut persisted, &registry, "http://127.0.0.1:18734").unwrap();
         assert_eq!(count, 0); // Not newly registered
 
         // But the file should now exist with the correct token
@@ -332,6 +618,15 @@ tables:
         assert!(content.contains("18734"));
     }
 
+    #[test]
+    fn test_httplib_content_uses_root_url() {
+        let content =
+            expected_httplib_content("components", "Parts", "my-token", "https://127.0.0.1:18734");
+        let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(json["source"]["root_url"], "https://127.0.0.1:18734");
+        assert_eq!(json["source"]["token"], "my-token");
+    }
+
     #[test]
     fn test_rewrites_httplib_with_stale_token() {
         let tmp = TempDir::new().unwrap();
@@ -344,6 +639,8 @@ tables:
             library_path: lib_dir.to_string_lossy().to_string(),
             name: "components".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
 
         // Write a stale .kicad_httplib with wrong token
@@ -353,11 +650,50 @@ tables:
         fs::write(&httplib, r#"{"source":{"token":"wrong-token"}}"#).unwrap();
 
         let registry = Arc::new(ProjectRegistry::new());
-        try_auto_register(project_dir, &mut persisted, &registry, 18734).unwrap();
+        try_auto_register(project_dir, &mut persisted, &registry, "http://127.0.0.1:18734").unwrap();
 
         // File should now have the correct token
         let content = fs::read_to_string(&httplib).unwrap();
         assert!(content.contains("correct-token"));
         assert!(!content.contains("wrong-token"));
     }
+
+    #[test]
+    fn test_rotate_token_rewrites_httplib() {
+        let tmp = TempDir::new().unwrap();
+        let (project_dir, lib_dir) = setup_project(&tmp);
+
+        let mut persisted = PersistedRegistry::default();
+        persisted.upsert(ProjectEntry {
+            token: "leaked-token".to_string(),
+            project_path: Some(project_dir.to_string_lossy().to_string()),
+            library_path: lib_dir.to_string_lossy().to_string(),
+            name: "components".to_string(),
+            description: None,
+            group: None,
+            access: Default::default(),
+        });
+
+        let grace = std::time::Duration::from_secs(3600);
+        let new_token = rotate_token(&mut persisted, "leaked-token", grace, "http://127.0.0.1:18734").unwrap();
+        assert_ne!(new_token, "leaked-token");
+
+        let entry = &persisted.projects[0];
+        assert_eq!(entry.token, new_token);
+        let previous = entry.access.previous_token.as_ref().unwrap();
+        assert_eq!(previous.token, "leaked-token");
+        assert!(previous.valid_until > chrono::Utc::now());
+
+        let httplib = project_dir.join(".kicodex").join("components.kicad_httplib");
+        let content = fs::read_to_string(&httplib).unwrap();
+        assert!(content.contains(&new_token));
+        assert!(!content.contains("leaked-token"));
+
+        // Rotating again without grace revokes both older tokens
+        let newest = rotate_token(&mut persisted, &new_token, std::time::Duration::ZERO, "http://127.0.0.1:18734").unwrap();
+        assert_eq!(persisted.projects[0].token, newest);
+        assert!(persisted.projects[0].access.previous_token.is_none());
+
+        assert!(rotate_token(&mut persisted, "leaked-token", grace, "http://127.0.0.1:18734").is_err());
+    }
 }
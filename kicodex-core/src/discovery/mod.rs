@@ -24,17 +24,17 @@ pub type OnActiveChangedCallback = Box<dyn Fn(&[std::path::PathBuf]) + Send + Sy
 pub struct DiscoveryEngine {
     persisted: PersistedRegistry,
     registry: Arc<ProjectRegistry>,
-    port: u16,
+    root_url: String,
     on_discovery: Option<OnDiscoveryCallback>,
     on_active_changed: Option<OnActiveChangedCallback>,
 }
 
 impl DiscoveryEngine {
-    pub fn new(persisted: PersistedRegistry, registry: Arc<ProjectRegistry>, port: u16) -> Self {
+    pub fn new(persisted: PersistedRegistry, registry: Arc<ProjectRegistry>, root_url: String) -> Self {
         Self {
             persisted,
             registry,
-            port,
+            root_url,
             on_discovery: None,
             on_active_changed: None,
         }
@@ -280,7 +280,7 @@ impl DiscoveryEngine {
     }
 
     fn try_register(&mut self, dir: &std::path::Path, lock_watcher: &LockWatcher) {
-        match auto_register::try_auto_register(dir, &mut self.persisted, &self.registry, self.port)
+        match auto_register::try_auto_register(dir, &mut self.persisted, &self.registry, &self.root_url)
         {
             Ok(count) if count > 0 => {
                 tracing::info!(
@@ -319,7 +319,7 @@ impl DiscoveryEngine {
                     &entry.name,
                     entry.description.as_deref(),
                     &entry.token,
-                    self.port,
+                    &self.root_url,
                 ) {
                     tracing::warn!("Failed to ensure httplib for {}: {}", entry.name, e);
                 }
@@ -336,7 +336,7 @@ mod tests {
     async fn test_discovery_engine_starts_without_crash() {
         let persisted = PersistedRegistry::default();
         let registry = Arc::new(ProjectRegistry::new());
-        let engine = DiscoveryEngine::new(persisted, registry, 18734);
+        let engine = DiscoveryEngine::new(persisted, registry, "http://127.0.0.1:18734".to_string());
         let handle = tokio::spawn(engine.start());
 
         // Let it run briefly then abort — just testing it doesn't panic on startup
@@ -1,8 +1,12 @@
+pub mod admin;
+pub mod config;
 pub mod data;
 pub mod discovery;
+pub mod metrics;
 pub mod middleware;
 pub mod models;
 pub mod registry;
 pub mod routes;
 pub mod server;
+pub mod tls;
 pub mod watcher;
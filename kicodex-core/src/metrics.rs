@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use dashmap::DashMap;
+
+use crate::registry::LibraryStatus;
+
+/// Upper bounds (in seconds) of the request duration histogram buckets.
+const DURATION_BUCKETS: [f64; 10] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+/// Number of parts in a response, set by the part routes so the metrics
+/// middleware can count parts served.
+#[derive(Debug, Clone, Copy)]
+pub struct PartsServed(pub usize);
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Cumulative counts per bucket of `DURATION_BUCKETS`.
+    buckets: [u64; DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Server metrics, rendered in the Prometheus text exposition format.
+///
+/// Metrics are recorded per token but labeled with the token's name (see
+/// `ProjectRegistry::token_names`), never the token itself, so the output is
+/// safe to hand to a shared monitoring system.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// (route, token, status) → request count
+    requests: DashMap<(String, String, u16), u64>,
+    /// (route, token) → request durations
+    durations: DashMap<(String, String), Histogram>,
+    /// token → parts served
+    parts_served: DashMap<String, u64>,
+    /// (token, succeeded) → reload count
+    reloads: DashMap<(String, bool), u64>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a request to `route` (the matched route pattern, not the raw path).
+    pub fn record_request(&self, route: &str, token: &str, status: u16, duration: Duration) {
+        *self
+            .requests
+            .entry((route.to_string(), token.to_string(), status))
+            .or_default() += 1;
+        self.durations
+            .entry((route.to_string(), token.to_string()))
+            .or_default()
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn record_parts_served(&self, token: &str, count: usize) {
+        *self.parts_served.entry(token.to_string()).or_default() += count as u64;
+    }
+
+    pub fn record_reload(&self, token: &str, succeeded: bool) {
+        *self
+            .reloads
+            .entry((token.to_string(), succeeded))
+            .or_default() += 1;
+    }
+
+    /// Render all metrics, with library size gauges taken from `libraries`.
+    /// `names` maps tokens to their label; metrics of tokens without a name
+    /// (removed since) are left out. Requests without a token are labeled
+    /// with an empty library.
+    pub fn render(&self, libraries: &[LibraryStatus], names: &HashMap<String, String>) -> String {
+        let mut out = String::new();
+        let name = |token: &str| match token {
+            "" => Some(String::new()),
+            token => names.get(token).cloned(),
+        };
+
+        header(&mut out, "kicodex_http_requests_total", "counter", "HTTP requests by route, library and status.");
+        let mut requests: Vec<_> = self
+            .requests
+            .iter()
+            .filter_map(|r| {
+                let (route, token, status) = r.key();
+                Some(((route.clone(), name(token)?, *status), *r.value()))
+            })
+            .collect();
+        requests.sort();
+        for ((route, library, status), count) in requests {
+            let _ = writeln!(
+                out,
+                "kicodex_http_requests_total{{route=\"{}\",library=\"{}\",status=\"{}\"}} {}",
+                escape(&route),
+                escape(&library),
+                status,
+                count
+            );
+        }
+
+        header(
+            &mut out,
+            "kicodex_http_request_duration_seconds",
+            "histogram",
+            "HTTP request latency by route and library.",
+        );
+        let mut keys: Vec<_> = self
+            .durations
+            .iter()
+            .filter_map(|r| {
+                let (route, token) = r.key();
+                Some(((route.clone(), name(token)?), r.key().clone()))
+            })
+            .collect();
+        keys.sort();
+        for ((route, library), key) in keys {
+            let Some(histogram) = self.durations.get(&key) else {
+                continue;
+            };
+            let labels = format!("route=\"{}\",library=\"{}\"", escape(&route), escape(&library));
+            for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "kicodex_http_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "kicodex_http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(out, "kicodex_http_request_duration_seconds_sum{{{labels}}} {}", histogram.sum);
+            let _ = writeln!(out, "kicodex_http_request_duration_seconds_count{{{labels}}} {}", histogram.count);
+        }
+
+        header(&mut out, "kicodex_parts_served_total", "counter", "Parts returned in responses, by library.");
+        let mut parts: Vec<_> = self
+            .parts_served
+            .iter()
+            .filter_map(|r| Some((name(r.key())?, *r.value())))
+            .collect();
+        parts.sort();
+        for (library, count) in parts {
+            let _ = writeln!(out, "kicodex_parts_served_total{{library=\"{}\"}} {}", escape(&library), count);
+        }
+
+        header(&mut out, "kicodex_library_reloads_total", "counter", "Library reloads by library and result.");
+        let mut reloads: Vec<_> = self
+            .reloads
+            .iter()
+            .filter_map(|r| {
+                let (token, succeeded) = r.key();
+                Some(((name(token)?, *succeeded), *r.value()))
+            })
+            .collect();
+        reloads.sort();
+        for ((library, succeeded), count) in reloads {
+            let result = if succeeded { "success" } else { "failure" };
+            let _ = writeln!(
+                out,
+                "kicodex_library_reloads_total{{library=\"{}\",result=\"{}\"}} {}",
+                escape(&library),
+                result,
+                count
+            );
+        }
+
+        let mut libraries: Vec<(&str, &LibraryStatus)> = libraries
+            .iter()
+            .filter_map(|status| Some((names.get(&status.token)?.as_str(), status)))
+            .collect();
+        libraries.sort_by_key(|(name, _)| *name);
+
+        header(&mut out, "kicodex_library_loaded", "gauge", "Whether a library is loaded and served.");
+        for (name, status) in &libraries {
+            let _ = writeln!(
+                out,
+                "kicodex_library_loaded{{library=\"{}\"}} {}",
+                escape(name),
+                u8::from(status.loaded)
+            );
+        }
+        header(&mut out, "kicodex_library_part_tables", "gauge", "Part tables served per library.");
+        for (name, status) in &libraries {
+            let _ = writeln!(
+                out,
+                "kicodex_library_part_tables{{library=\"{}\"}} {}",
+                escape(name),
+                status.part_tables
+            );
+        }
+        header(&mut out, "kicodex_library_parts", "gauge", "Parts served per library.");
+        for (name, status) in &libraries {
+            let _ = writeln!(out, "kicodex_library_parts{{library=\"{}\"}} {}", escape(name), status.parts);
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escape a label value for the text exposition format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters_and_histogram() {
+        let metrics = Metrics::new();
+        metrics.record_request("/v1/parts/{partId}", "token-1", 200, Duration::from_millis(3));
+        metrics.record_request("/v1/parts/{partId}", "token-1", 200, Duration::from_millis(30));
+        metrics.record_parts_served("token-1", 2);
+        metrics.record_reload("token-1", false);
+        metrics.record_reload("removed-token", true);
+
+        let names = HashMap::from([("token-1".to_string(), "passives".to_string())]);
+        let out = metrics.render(&[], &names);
+        assert!(out.contains(
+            r#"kicodex_http_requests_total{route="/v1/parts/{partId}",library="passives",status="200"} 2"#
+        ));
+        assert!(out.contains(
+            r#"kicodex_http_request_duration_seconds_bucket{route="/v1/parts/{partId}",library="passives",le="0.005"} 1"#
+        ));
+        assert!(out.contains(
+            r#"kicodex_http_request_duration_seconds_bucket{route="/v1/parts/{partId}",library="passives",le="+Inf"} 2"#
+        ));
+        assert!(out.contains(r#"kicodex_parts_served_total{library="passives"} 2"#));
+        assert!(out.contains(r#"kicodex_library_reloads_total{library="passives",result="failure"} 1"#));
+        assert!(out.contains("# TYPE kicodex_http_request_duration_seconds histogram"));
+        assert!(!out.contains("token-1"));
+        assert!(!out.contains("success"));
+    }
+
+    #[test]
+    fn test_escape_label_values() {
+        assert_eq!(escape(r#"a "b" \c"#), r#"a \"b\" \\c"#);
+        assert_eq!(escape("two\nlines"), r"two\nlines");
+    }
+}
@@ -1,10 +1,12 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use axum::extract::{Request, State};
-use axum::http::StatusCode;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{header, HeaderValue, Method, StatusCode};
 use axum::middleware::Next;
-use axum::response::Response;
+use axum::response::{IntoResponse, Response};
 
+use crate::metrics::PartsServed;
 use crate::registry::ProjectRegistry;
 use crate::server::LoadedLibrary;
 
@@ -13,40 +15,275 @@ use crate::server::LoadedLibrary;
 #[derive(Clone)]
 pub struct AuthenticatedLibrary(pub Arc<LoadedLibrary>);
 
+/// The token the request was authenticated with, inserted alongside
+/// `AuthenticatedLibrary` and into the response for the metrics middleware.
+#[derive(Clone)]
+pub struct AuthenticatedToken(pub String);
+
+/// Who may reach the server, beyond the per-token checks.
+#[derive(Debug, Clone, Default)]
+pub struct AccessPolicy {
+    /// Always require a valid token, even when only one library is registered.
+    pub strict: bool,
+    /// Host names accepted in the `Host` header (protection against DNS
+    /// rebinding). Ports are ignored. Empty accepts any host.
+    pub allowed_hosts: Vec<String>,
+}
+
+impl AccessPolicy {
+    /// Whether a `Host` header value is allowed.
+    pub fn allows_host(&self, host: &str) -> bool {
+        if self.allowed_hosts.is_empty() {
+            return true;
+        }
+        let host = strip_port(host);
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| strip_port(allowed).eq_ignore_ascii_case(host))
+    }
+}
+
+/// Strip the port from a host (`localhost:18734`, `[::1]:18734`), along with
+/// the brackets around an IPv6 address.
+fn strip_port(host: &str) -> &str {
+    let host = host.trim();
+    if let Some(rest) = host.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    match host.rsplit_once(':') {
+        // More than one colon is a bare IPv6 address
+        Some((name, port)) if !name.contains(':') && port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    }
+}
+
+/// State of the auth middleware.
+#[derive(Clone)]
+pub struct AuthState {
+    pub registry: Arc<ProjectRegistry>,
+    pub policy: Arc<AccessPolicy>,
+}
+
 /// Middleware that extracts the `Authorization: Token <value>` header,
-/// looks up the project in the registry, and inserts an `AuthenticatedLibrary`
-/// extension. Returns 401 if the token is missing or unknown.
+/// looks up the project in the registry, and inserts `AuthenticatedLibrary`
+/// and `AuthenticatedToken` extensions. Returns 401 if the token is missing,
+/// unknown or expired, and 403 for a non-read request with a read-only token.
+/// A rotated-out token is accepted (as its replacement) during its grace window.
 ///
-/// When the registry contains exactly one project, the token check is skipped
-/// (single-library mode for Phase 1 backwards compatibility).
+/// When the registry contains exactly one project and the policy is not
+/// strict, the token check is skipped (single-library mode for Phase 1
+/// backwards compatibility).
 pub async fn auth_middleware(
-    State(registry): State<Arc<ProjectRegistry>>,
+    State(state): State<AuthState>,
     mut req: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
+    let registry = &state.registry;
     let tokens = registry.tokens();
 
     // Single-project mode: skip auth, use the only registered library
-    if tokens.len() == 1 {
-        if let Some(library) = registry.get(&tokens[0]) {
-            req.extensions_mut().insert(AuthenticatedLibrary(library));
-            return Ok(next.run(req).await);
+    let single = (!state.policy.strict && tokens.len() == 1)
+        .then(|| tokens[0].clone())
+        .and_then(|token| Some((registry.get(&token)?, token)));
+
+    let (library, token) = match single {
+        Some(single) => single,
+        None => {
+            // Multi-project or strict mode: require Authorization header
+            let auth_header = req
+                .headers()
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .ok_or(StatusCode::UNAUTHORIZED)?;
+
+            let mut token = auth_header
+                .strip_prefix("Token ")
+                .ok_or(StatusCode::UNAUTHORIZED)?
+                .to_string();
+
+            if registry.get(&token).is_none() {
+                // A rotated-out token still works during its grace window
+                let current = registry
+                    .replacement_token(&token, chrono::Utc::now())
+                    .ok_or(StatusCode::UNAUTHORIZED)?;
+                tracing::warn!(
+                    "Deprecated token {}... used; it was rotated and will stop working soon",
+                    &token[..token.len().min(8)]
+                );
+                token = current;
+            }
+            (registry.get(&token).ok_or(StatusCode::UNAUTHORIZED)?, token)
+        }
+    };
+
+    if let Some(access) = registry.token_access(&token) {
+        if access.is_expired(chrono::Utc::now()) {
+            tracing::warn!("Rejected expired token {}...", &token[..token.len().min(8)]);
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        if access.read_only && req.method() != Method::GET && req.method() != Method::HEAD {
+            return Err(StatusCode::FORBIDDEN);
         }
     }
 
-    // Multi-project mode: require Authorization header
-    let auth_header = req
+    req.extensions_mut().insert(AuthenticatedLibrary(library));
+    req.extensions_mut().insert(AuthenticatedToken(token.clone()));
+
+    let mut response = next.run(req).await;
+    response.extensions_mut().insert(AuthenticatedToken(token));
+    Ok(response)
+}
+
+/// Route label of requests that matched no route.
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Middleware recording request counts and latencies per route and token
+/// in the registry's metrics, plus the parts each response carries. Runs
+/// before auth so rejected requests are counted too; auth puts the token
+/// into the response, and requests it rejected have an empty token.
+pub async fn metrics_middleware(
+    State(registry): State<Arc<ProjectRegistry>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        // Not the raw path: clients could create unbounded label values
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+    let start = Instant::now();
+    let response = next.run(req).await;
+    let token = response
+        .extensions()
+        .get::<AuthenticatedToken>()
+        .map(|AuthenticatedToken(token)| token.as_str())
+        .unwrap_or_default();
+    let metrics = registry.metrics();
+    metrics.record_request(&route, token, response.status().as_u16(), start.elapsed());
+    if let Some(PartsServed(count)) = response.extensions().get::<PartsServed>() {
+        metrics.record_parts_served(token, *count);
+    }
+    response
+}
+
+/// Middleware that rejects requests whose `Host` header is not allowed by
+/// the policy with 403.
+pub async fn host_allowlist_middleware(
+    State(policy): State<Arc<AccessPolicy>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let host = req
         .headers()
-        .get("authorization")
+        .get(header::HOST)
         .and_then(|v| v.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+        .or_else(|| req.uri().host());
+    match host {
+        Some(host) if policy.allows_host(host) => Ok(next.run(req).await),
+        _ => {
+            tracing::warn!("Rejected request for host {:?}", host);
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
 
-    let token = auth_header
-        .strip_prefix("Token ")
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+/// Middleware that answers conditional requests for the authenticated library.
+///
+/// Successful responses carry an `ETag` (the library's content hash) and a
+/// `Last-Modified` date. A request whose `If-None-Match` matches the ETag, or
+/// (without `If-None-Match`) whose `If-Modified-Since` is not older than the
+/// library, gets `304 Not Modified` without running the route.
+///
+/// Must run inside `auth_middleware`, which provides the library.
+pub async fn conditional_get_middleware(req: Request, next: Next) -> Response {
+    let Some(AuthenticatedLibrary(library)) = req.extensions().get::<AuthenticatedLibrary>().cloned()
+    else {
+        return next.run(req).await;
+    };
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return next.run(req).await;
+    }
 
-    let library = registry.get(token).ok_or(StatusCode::UNAUTHORIZED)?;
-    req.extensions_mut().insert(AuthenticatedLibrary(library));
+    let etag = format!("\"{:016x}\"", library.content_hash);
+    // HTTP dates have one-second resolution
+    let modified = truncate_to_seconds(library.modified);
+    let last_modified = httpdate::fmt_http_date(modified);
+
+    let headers = req.headers();
+    let not_modified = match headers.get(header::IF_NONE_MATCH) {
+        Some(value) => value
+            .to_str()
+            .map(|v| etag_matches(v, &etag))
+            .unwrap_or(false),
+        None => headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| httpdate::parse_http_date(v).ok())
+            .is_some_and(|since| modified <= since),
+    };
 
-    Ok(next.run(req).await)
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        next.run(req).await
+    };
+    if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+        let headers = response.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            headers.insert(header::ETAG, value);
+        }
+        if let Ok(value) = HeaderValue::from_str(&last_modified) {
+            headers.insert(header::LAST_MODIFIED, value);
+        }
+    }
+    response
+}
+
+/// Whether an `If-None-Match` header value matches `etag`. Weak validators
+/// (`W/"..."`) compare equal to their strong form.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since_epoch) => UNIX_EPOCH + Duration::from_secs(since_epoch.as_secs()),
+        Err(_) => time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_host() {
+        assert!(AccessPolicy::default().allows_host("anything.example"));
+
+        let policy = AccessPolicy {
+            strict: true,
+            allowed_hosts: vec!["localhost".to_string(), "::1".to_string(), "kicodex.lan:80".to_string()],
+        };
+        assert!(policy.allows_host("localhost"));
+        assert!(policy.allows_host("LOCALHOST:18734"));
+        assert!(policy.allows_host("[::1]:18734"));
+        assert!(policy.allows_host("kicodex.lan"));
+        assert!(!policy.allows_host("evil.example"));
+        assert!(!policy.allows_host("localhost.evil.example:18734"));
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = "\"00000000000000ff\"";
+        assert!(etag_matches(etag, etag));
+        assert!(etag_matches("W/\"00000000000000ff\"", etag));
+        assert!(etag_matches("\"abc\", \"00000000000000ff\"", etag));
+        assert!(etag_matches("*", etag));
+        assert!(!etag_matches("\"abc\"", etag));
+        assert!(!etag_matches("00000000000000ff", etag));
+    }
 }
@@ -1,5 +1,7 @@
 use indexmap::IndexMap;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::registry::ProjectEntry;
 
 /// Response for GET /v1/
 #[derive(Debug, Serialize)]
@@ -14,6 +16,9 @@ pub struct Category {
     pub id: String,
     pub name: String,
     pub description: String,
+    /// Longer notes from library.yaml (not used by KiCad).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
 }
 
 /// A part summary (returned in category listing).
@@ -44,3 +49,43 @@ pub struct PartDetail {
     pub exclude_from_sim: String,
     pub fields: IndexMap<String, FieldValue>,
 }
+
+/// A part table named in a library event.
+#[derive(Debug, Serialize)]
+pub struct PartTableRef {
+    pub id: String,
+    pub name: String,
+}
+
+/// Data of a server-sent event on GET /v1/events. The event name is one of
+/// `inserted`, `reloaded`, `reload_failed` or `removed`.
+#[derive(Debug, Serialize)]
+pub struct LibraryEvent {
+    pub library: String,
+    /// Part tables whose content changed (`reloaded` only).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_tables: Vec<PartTableRef>,
+    /// Why the reload failed (`reload_failed` only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Body of POST /internal/register.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegisterRequest {
+    pub entries: Vec<ProjectEntry>,
+}
+
+/// Body of POST /internal/unregister.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnregisterRequest {
+    pub tokens: Vec<String>,
+}
+
+/// Body of POST /internal/reload. Without a token, every registered
+/// library is reloaded.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReloadRequest {
+    #[serde(default)]
+    pub token: Option<String>,
+}
@@ -1,11 +1,16 @@
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio::sync::broadcast;
 
-use crate::server::{LoadedLibrary, ServerError};
+use crate::data::project::{self, PartFilter};
+use crate::metrics::Metrics;
+use crate::server::{LoadDiagnostic, LoadMode, LoadedLibrary, ReloadScope, ReloadStats, ServerError};
 
 #[derive(Debug, Error)]
 pub enum RegistryError {
@@ -18,7 +23,7 @@ pub enum RegistryError {
 }
 
 /// A persisted project entry in the registry JSON file.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProjectEntry {
     pub token: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -27,6 +32,61 @@ pub struct ProjectEntry {
     pub name: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Name of the aggregated KiCad library this entry is served in. Entries
+    /// of one group share a token and a single `.kicad_httplib` file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Restrictions on the entry's token.
+    #[serde(flatten)]
+    pub access: TokenAccess,
+}
+
+/// Optional restrictions on a token, stored alongside its `ProjectEntry`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenAccess {
+    /// Only allow reading (`GET`/`HEAD` requests) with this token.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    /// When the token stops being accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The token this one replaced, still accepted during its grace window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_token: Option<RetiredToken>,
+}
+
+/// A rotated-out token that keeps working until `valid_until`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetiredToken {
+    pub token: String,
+    pub valid_until: DateTime<Utc>,
+}
+
+impl TokenAccess {
+    /// Whether the token has expired at `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+impl ProjectEntry {
+    /// Name of the KiCad library (and `.kicad_httplib` file) serving this entry.
+    pub fn httplib_name(&self) -> &str {
+        self.group.as_deref().unwrap_or(&self.name)
+    }
+
+    /// The project's part filter for this library, read from its kicodex.yaml.
+    /// Standalone libraries and unreadable configs are unfiltered.
+    pub fn part_filter(&self) -> PartFilter {
+        let Some(project_path) = self.project_path.as_deref() else {
+            return PartFilter::default();
+        };
+        project::load_project_config(Path::new(project_path))
+            .ok()
+            .and_then(|config| config.libraries.into_iter().find(|l| l.name == self.name))
+            .map(|l| l.filter)
+            .unwrap_or_default()
+    }
 }
 
 /// Persistent registry stored as JSON on disk.
@@ -92,11 +152,139 @@ impl PersistedRegistry {
     pub fn find_by_token(&self, token: &str) -> Option<&ProjectEntry> {
         self.projects.iter().find(|p| p.token == token)
     }
+
+    /// Compare two versions of the registry's entries by token.
+    pub fn diff(old: &[ProjectEntry], new: &[ProjectEntry]) -> RegistryChanges {
+        let entries_of = |entries: &[ProjectEntry], token: &str| -> Vec<ProjectEntry> {
+            entries.iter().filter(|e| e.token == token).cloned().collect()
+        };
+
+        let mut changes = RegistryChanges::default();
+        for entry in old {
+            if !new.iter().any(|e| e.token == entry.token)
+                && !changes.removed_tokens.contains(&entry.token)
+            {
+                changes.removed_tokens.push(entry.token.clone());
+            }
+        }
+        let mut seen: Vec<&str> = Vec::new();
+        for entry in new {
+            if seen.contains(&entry.token.as_str()) {
+                continue;
+            }
+            seen.push(&entry.token);
+            let new_entries = entries_of(new, &entry.token);
+            if entries_of(old, &entry.token) != new_entries {
+                changes.changed_entries.extend(new_entries);
+            }
+        }
+        changes
+    }
+}
+
+/// Differences between two versions of the persisted registry.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RegistryChanges {
+    /// Tokens that are no longer registered.
+    pub removed_tokens: Vec<String>,
+    /// All entries of tokens that are new or whose entries changed.
+    pub changed_entries: Vec<ProjectEntry>,
+}
+
+/// A member library of an aggregated token.
+pub struct AggregateMember {
+    pub name: String,
+    pub path: PathBuf,
+    pub filter: PartFilter,
+    pub library: Arc<LoadedLibrary>,
+}
+
+/// Several libraries served under one token as a single merged library.
+struct Aggregate {
+    name: String,
+    members: Vec<AggregateMember>,
 }
 
+impl Aggregate {
+    fn merged(&self) -> LoadedLibrary {
+        let members: Vec<(&str, &LoadedLibrary)> = self
+            .members
+            .iter()
+            .map(|m| (m.name.as_str(), m.library.as_ref()))
+            .collect();
+        crate::server::merge_libraries(&self.name, &members)
+    }
+}
+
+/// What happened to the library served under a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryEventKind {
+    Inserted,
+    /// The library was reloaded; lists the (category ID, name) of part
+    /// tables whose content changed.
+    Reloaded { changed_tables: Vec<(String, String)> },
+    /// The library failed to reload and the previous version is still served.
+    ReloadFailed { error: String },
+    Removed,
+}
+
+impl RegistryEventKind {
+    /// Event name used on the `/v1/events` stream.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegistryEventKind::Inserted => "inserted",
+            RegistryEventKind::Reloaded { .. } => "reloaded",
+            RegistryEventKind::ReloadFailed { .. } => "reload_failed",
+            RegistryEventKind::Removed => "removed",
+        }
+    }
+}
+
+/// A change to the runtime registry, broadcast to subscribers.
+#[derive(Debug, Clone)]
+pub struct RegistryEvent {
+    pub token: String,
+    /// Name of the library served under the token.
+    pub library: String,
+    pub kind: RegistryEventKind,
+}
+
+/// Load status of one token, as reported by the admin API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryStatus {
+    pub token: String,
+    pub name: String,
+    pub library_paths: Vec<String>,
+    /// Whether a library is being served. A failed reload keeps serving the
+    /// previous version, so `loaded` and `error` can both be set.
+    pub loaded: bool,
+    pub part_tables: usize,
+    pub parts: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Part tables left out of the served library because they failed to load.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<LoadDiagnostic>,
+}
+
+/// Events buffered per subscriber before it starts lagging.
+const EVENT_CAPACITY: usize = 64;
+
 /// Runtime registry holding loaded libraries keyed by auth token.
 pub struct ProjectRegistry {
     libraries: DashMap<String, Arc<LoadedLibrary>>,
+    /// Member libraries of aggregated tokens, kept so one member can be reloaded.
+    aggregates: DashMap<String, Aggregate>,
+    /// Project part filters of non-aggregated tokens, reapplied on reload.
+    filters: DashMap<String, PartFilter>,
+    /// Persisted entries behind each token, when loaded from the registry file.
+    entries: DashMap<String, Vec<ProjectEntry>>,
+    /// Why a token's library (or some aggregate members) failed to load.
+    load_errors: DashMap<String, String>,
+    events: broadcast::Sender<RegistryEvent>,
+    metrics: Metrics,
+    /// Whether loads and reloads may write IDs back to library files.
+    load_mode: LoadMode,
 }
 
 impl Default for ProjectRegistry {
@@ -110,20 +298,133 @@ impl ProjectRegistry {
     pub fn new() -> Self {
         Self {
             libraries: DashMap::new(),
+            aggregates: DashMap::new(),
+            filters: DashMap::new(),
+            entries: DashMap::new(),
+            load_errors: DashMap::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            metrics: Metrics::new(),
+            load_mode: LoadMode::default(),
         }
     }
 
+    /// Load and reload libraries in `mode`, e.g. `LoadMode::ReadOnly` to never
+    /// write to library files.
+    pub fn with_load_mode(mut self, mode: LoadMode) -> Self {
+        self.load_mode = mode;
+        self
+    }
+
+    /// Request, part and reload counters of the server using this registry.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Subscribe to insert, reload and remove events for all tokens.
+    pub fn subscribe(&self) -> broadcast::Receiver<RegistryEvent> {
+        self.events.subscribe()
+    }
+
+    fn notify(&self, token: &str, library: &str, kind: RegistryEventKind) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(RegistryEvent {
+            token: token.to_string(),
+            library: library.to_string(),
+            kind,
+        });
+    }
+
     /// Insert a loaded library under the given token.
     pub fn insert(&self, token: &str, library: LoadedLibrary) {
-        self.libraries.insert(token.to_string(), Arc::new(library));
+        self.insert_with_filter(token, library, PartFilter::default());
+    }
+
+    /// Insert a library loaded with a project's part filter. The filter is
+    /// reapplied whenever the library is reloaded.
+    pub fn insert_with_filter(&self, token: &str, library: LoadedLibrary, filter: PartFilter) {
+        self.aggregates.remove(token);
+        self.filters.insert(token.to_string(), filter);
+        self.load_errors.remove(token);
+        let name = library.name.clone();
+        self.store(token, library);
+        self.notify(token, &name, RegistryEventKind::Inserted);
+    }
+
+    /// Insert several libraries under one token, merged into a single library
+    /// named `name`.
+    pub fn insert_aggregate(&self, token: &str, name: &str, members: Vec<AggregateMember>) {
+        let aggregate = Aggregate {
+            name: name.to_string(),
+            members,
+        };
+        self.filters.remove(token);
+        self.load_errors.remove(token);
+        self.store(token, aggregate.merged());
+        self.aggregates.insert(token.to_string(), aggregate);
+        self.notify(token, name, RegistryEventKind::Inserted);
     }
 
     /// Build a runtime registry from a persisted registry, loading all libraries.
     pub fn from_persisted(persisted: &PersistedRegistry) -> Result<Self, RegistryError> {
-        let libraries = DashMap::new();
-        for entry in &persisted.projects {
+        let registry = Self::new();
+        registry.load_entries(&persisted.projects);
+        Ok(registry)
+    }
+
+    /// Load the libraries of persisted entries, replacing whatever their tokens
+    /// served before. Grouped entries sharing a token are merged into one
+    /// library. Libraries that fail to load are logged, reported by
+    /// `status()` and served as a placeholder carrying the error.
+    pub fn load_entries(&self, entries: &[ProjectEntry]) {
+        let mut groups: Vec<(&str, &str, Vec<AggregateMember>, Vec<String>)> = Vec::new();
+        for entry in entries {
+            let Some(group) = entry.group.as_deref() else {
+                continue;
+            };
+            let idx = match groups.iter().position(|(token, ..)| *token == entry.token) {
+                Some(idx) => idx,
+                None => {
+                    groups.push((&entry.token, group, Vec::new(), Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            let library_path = PathBuf::from(&entry.library_path);
+            let filter = entry.part_filter();
+            match crate::server::load_library_with_mode(&library_path, &filter, self.load_mode) {
+                Ok(library) => groups[idx].2.push(AggregateMember {
+                    name: entry.name.clone(),
+                    path: library_path,
+                    filter,
+                    library: Arc::new(library),
+                }),
+                Err(e) => {
+                    tracing::error!("Failed to load library '{}' of group '{}': {}", entry.name, group, e);
+                    groups[idx].3.push(format!("{}: {}", entry.name, e));
+                }
+            }
+        }
+        for (token, group, members, errors) in groups {
+            if members.is_empty() {
+                self.remove(token);
+                self.insert_load_error(token, group, errors.join("; "));
+                continue;
+            }
+            tracing::info!(
+                "Loaded {} libraries as '{}' (token: {}...)",
+                members.len(),
+                group,
+                &token[..token.len().min(8)]
+            );
+            self.insert_aggregate(token, group, members);
+            if !errors.is_empty() {
+                self.load_errors.insert(token.to_string(), errors.join("; "));
+            }
+        }
+
+        for entry in entries.iter().filter(|e| e.group.is_none()) {
             let library_path = PathBuf::from(&entry.library_path);
-            match crate::server::load_library(&library_path) {
+            let filter = entry.part_filter();
+            match crate::server::load_library_with_mode(&library_path, &filter, self.load_mode) {
                 Ok(library) => {
                     tracing::info!(
                         "Loaded library '{}' for project '{}' (token: {}...)",
@@ -131,14 +432,133 @@ impl ProjectRegistry {
                         entry.name,
                         &entry.token[..entry.token.len().min(8)]
                     );
-                    libraries.insert(entry.token.clone(), Arc::new(library));
+                    self.insert_with_filter(&entry.token, library, filter);
                 }
                 Err(e) => {
                     tracing::error!("Failed to load library for project '{}': {}", entry.name, e);
+                    self.remove(&entry.token);
+                    self.filters.insert(entry.token.clone(), filter);
+                    self.insert_load_error(&entry.token, &entry.name, e.to_string());
                 }
             }
         }
-        Ok(Self { libraries })
+
+        for entry in entries {
+            self.entries.remove(&entry.token);
+        }
+        for entry in entries {
+            self.entries
+                .entry(entry.token.clone())
+                .or_default()
+                .push(entry.clone());
+        }
+    }
+
+    /// The persisted entries loaded through `load_entries`.
+    pub fn entries(&self) -> Vec<ProjectEntry> {
+        self.entries
+            .iter()
+            .flat_map(|r| r.value().clone())
+            .collect()
+    }
+
+    /// Access restrictions of a token loaded through `load_entries`.
+    /// Tokens inserted directly have none.
+    pub fn token_access(&self, token: &str) -> Option<TokenAccess> {
+        let entries = self.entries.get(token)?;
+        entries.value().first().map(|e| e.access.clone())
+    }
+
+    /// The current token replacing a rotated-out one, while the old token's
+    /// grace window is still open at `now`.
+    pub fn replacement_token(&self, retired: &str, now: DateTime<Utc>) -> Option<String> {
+        self.entries.iter().find_map(|r| {
+            r.value().iter().find_map(|entry| {
+                let previous = entry.access.previous_token.as_ref()?;
+                (previous.token == retired && previous.valid_until > now).then(|| entry.token.clone())
+            })
+        })
+    }
+
+    /// Load status of every token that is served or failed to load.
+    pub fn status(&self) -> Vec<LibraryStatus> {
+        let mut tokens: Vec<String> = self.tokens();
+        for r in self.load_errors.iter() {
+            if !tokens.contains(r.key()) {
+                tokens.push(r.key().clone());
+            }
+        }
+        tokens.sort();
+
+        tokens
+            .into_iter()
+            .map(|token| {
+                let entries = self.entries.get(&token).map(|r| r.value().clone()).unwrap_or_default();
+                let served = self.get(&token);
+                let name = match (&served, entries.first()) {
+                    (Some(library), _) => library.name.clone(),
+                    (None, Some(entry)) => entry.httplib_name().to_string(),
+                    (None, None) => String::new(),
+                };
+                let library = served.filter(|l| l.load_error.is_none());
+                LibraryStatus {
+                    name,
+                    library_paths: entries.iter().map(|e| e.library_path.clone()).collect(),
+                    loaded: library.is_some(),
+                    part_tables: library.as_ref().map_or(0, |l| l.part_tables.len()),
+                    parts: library.as_ref().map_or(0, |l| l.part_index.len()),
+                    error: self.load_errors.get(&token).map(|e| e.value().clone()),
+                    diagnostics: library.as_ref().map(|l| l.diagnostics.clone()).unwrap_or_default(),
+                    token,
+                }
+            })
+            .collect()
+    }
+
+    /// A unique, human-readable name for every token in `status`, used to
+    /// label its metrics instead of the secret token. Names are KiCad library
+    /// names; tokens sharing one are told apart by their project directory,
+    /// then by a number.
+    pub fn token_names(&self) -> HashMap<String, String> {
+        let mut names: Vec<(String, String)> = self
+            .status()
+            .into_iter()
+            .map(|status| {
+                let name = self
+                    .entries
+                    .get(&status.token)
+                    .and_then(|entries| entries.first().map(|e| e.httplib_name().to_string()))
+                    .unwrap_or(status.name);
+                (status.token, name)
+            })
+            .collect();
+
+        let duplicated = |names: &[(String, String)]| -> HashSet<String> {
+            let mut seen = HashSet::new();
+            names
+                .iter()
+                .filter(|(_, name)| !seen.insert(name.clone()))
+                .map(|(_, name)| name.clone())
+                .collect()
+        };
+        let shared = duplicated(&names);
+        for (token, name) in names.iter_mut().filter(|(_, name)| shared.contains(name)) {
+            let project = self.entries.get(token.as_str()).and_then(|entries| {
+                let project_path = entries.first()?.project_path.clone()?;
+                Some(Path::new(&project_path).file_name()?.to_string_lossy().to_string())
+            });
+            if let Some(project) = project {
+                *name = format!("{} ({})", name, project);
+            }
+        }
+        let shared = duplicated(&names);
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for (_, name) in names.iter_mut().filter(|(_, name)| shared.contains(name)) {
+            let count = counts.entry(name.clone()).or_default();
+            *count += 1;
+            *name = format!("{} #{}", name, count);
+        }
+        names.into_iter().collect()
     }
 
     /// Get a loaded library by auth token.
@@ -147,15 +567,150 @@ impl ProjectRegistry {
     }
 
     /// Reload a library for the given token from the given path.
+    /// The library and its part index are fully rebuilt before being swapped in,
+    /// so concurrent requests see either the old or the new state, never a mix.
+    ///
+    /// For an aggregated token, only the member at `library_path` is reloaded
+    /// before the merged library is rebuilt.
     pub fn reload(&self, token: &str, library_path: &Path) -> Result<(), ServerError> {
-        let library = crate::server::load_library(library_path)?;
+        self.reload_scoped(token, library_path, &ReloadScope::Full)
+            .map(|_| ())
+    }
+
+    /// Reload after the `changed` files under `library_path` were modified,
+    /// loading only the part tables that depend on them again. Returns `None`
+    /// when no part table does, or when `library_path` is not served under
+    /// the token.
+    pub fn reload_changed(
+        &self,
+        token: &str,
+        library_path: &Path,
+        changed: &[PathBuf],
+    ) -> Result<Option<ReloadStats>, ServerError> {
+        let scope = crate::server::reload_scope(library_path, changed);
+        if scope == ReloadScope::Unaffected {
+            return Ok(None);
+        }
+        self.reload_scoped(token, library_path, &scope)
+    }
+
+    fn reload_scoped(
+        &self,
+        token: &str,
+        library_path: &Path,
+        scope: &ReloadScope,
+    ) -> Result<Option<ReloadStats>, ServerError> {
+        let previous = self.get(token);
+        match self.load_for_reload(token, library_path, scope) {
+            Ok(Some((library, stats))) => {
+                let changed_tables = match &previous {
+                    Some(previous) => crate::server::changed_part_tables(previous, &library),
+                    None => library
+                        .part_tables
+                        .iter()
+                        .map(|ct| (ct.id.clone(), ct.name.clone()))
+                        .collect(),
+                };
+                let name = library.name.clone();
+                self.store(token, library);
+                self.load_errors.remove(token);
+                self.metrics.record_reload(token, true);
+                self.notify(token, &name, RegistryEventKind::Reloaded { changed_tables });
+                Ok(Some(stats))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                let name = match &previous {
+                    Some(previous) => previous.name.clone(),
+                    None => library_path.display().to_string(),
+                };
+                self.metrics.record_reload(token, false);
+                // Keep serving the last good version; only a placeholder is replaced
+                if previous.as_ref().is_none_or(|p| p.load_error.is_some()) {
+                    self.insert_load_error(token, &name, e.to_string());
+                } else {
+                    self.load_errors.insert(token.to_string(), e.to_string());
+                }
+                self.notify(
+                    token,
+                    &name,
+                    RegistryEventKind::ReloadFailed {
+                        error: e.to_string(),
+                    },
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Load the library to swap in on reload, or `None` when `library_path`
+    /// is not a member of the aggregated token.
+    fn load_for_reload(
+        &self,
+        token: &str,
+        library_path: &Path,
+        scope: &ReloadScope,
+    ) -> Result<Option<(LoadedLibrary, ReloadStats)>, ServerError> {
+        if let Some(mut aggregate) = self.aggregates.get_mut(token) {
+            let Some(member) = aggregate
+                .members
+                .iter_mut()
+                .find(|m| same_path(&m.path, library_path))
+            else {
+                tracing::warn!(
+                    "{} is not a member of aggregated library '{}'",
+                    library_path.display(),
+                    aggregate.name
+                );
+                return Ok(None);
+            };
+            let (library, stats) = crate::server::reload_library(
+                library_path,
+                &member.filter,
+                self.load_mode,
+                Some(&member.library),
+                scope,
+            )?;
+            member.library = Arc::new(library);
+            return Ok(Some((aggregate.merged(), stats)));
+        }
+        let filter = self
+            .filters
+            .get(token)
+            .map(|f| f.value().clone())
+            .unwrap_or_default();
+        let previous = self.get(token);
+        crate::server::reload_library(library_path, &filter, self.load_mode, previous.as_deref(), scope)
+            .map(Some)
+    }
+
+    /// Serve a placeholder carrying `error` for a token whose library failed
+    /// to load, and report the error through `status()`.
+    fn insert_load_error(&self, token: &str, name: &str, error: String) {
+        self.store(token, crate::server::load_error_library(name, &error));
+        self.load_errors.insert(token.to_string(), error);
+    }
+
+    /// Swap in a library for a token. When its content is unchanged, the
+    /// previous modification time is kept so conditional requests still match.
+    fn store(&self, token: &str, mut library: LoadedLibrary) {
+        if let Some(previous) = self.libraries.get(token) {
+            if previous.content_hash == library.content_hash {
+                library.modified = previous.modified;
+            }
+        }
         self.libraries.insert(token.to_string(), Arc::new(library));
-        Ok(())
     }
 
     /// Remove a library by its auth token.
     pub fn remove(&self, token: &str) {
-        self.libraries.remove(token);
+        self.aggregates.remove(token);
+        self.filters.remove(token);
+        self.entries.remove(token);
+        self.load_errors.remove(token);
+        if let Some((_, library)) = self.libraries.remove(token) {
+            self.notify(token, &library.name, RegistryEventKind::Removed);
+        }
     }
 
     /// Get all tokens currently registered.
@@ -164,6 +719,11 @@ impl ProjectRegistry {
     }
 }
 
+/// Compare two paths, falling back to their canonical forms.
+fn same_path(a: &Path, b: &Path) -> bool {
+    a == b || matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +741,8 @@ mod tests {
             library_path: "/home/user/project1/libs/components".to_string(),
             name: "Project 1".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
 
         registry.save(&path).unwrap();
@@ -199,6 +761,8 @@ mod tests {
             library_path: "/project/libs".to_string(),
             name: "Project".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
         registry.upsert(ProjectEntry {
             token: "token2".to_string(),
@@ -206,6 +770,8 @@ mod tests {
             library_path: "/project/libs".to_string(),
             name: "Project Updated".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
 
         assert_eq!(registry.projects.len(), 1);
@@ -213,6 +779,40 @@ mod tests {
         assert_eq!(registry.projects[0].name, "Project Updated");
     }
 
+    #[test]
+    fn test_persisted_registry_diff() {
+        let entry = |token: &str, library_path: &str, group: Option<&str>| ProjectEntry {
+            token: token.to_string(),
+            project_path: Some("/p".to_string()),
+            library_path: library_path.to_string(),
+            name: library_path.trim_start_matches("/libs/").to_string(),
+            description: None,
+            group: group.map(|g| g.to_string()),
+            access: Default::default(),
+        };
+        let old = vec![
+            entry("a", "/libs/a", None),
+            entry("b", "/libs/b", None),
+            entry("g", "/libs/g1", Some("all")),
+            entry("g", "/libs/g2", Some("all")),
+        ];
+        let new = vec![
+            entry("a", "/libs/a", None),
+            entry("c", "/libs/c", None),
+            entry("g", "/libs/g1", Some("all")),
+        ];
+
+        let changes = PersistedRegistry::diff(&old, &new);
+        assert_eq!(changes.removed_tokens, vec!["b".to_string()]);
+        // A changed group is reloaded with all of its remaining entries
+        assert_eq!(
+            changes.changed_entries,
+            vec![entry("c", "/libs/c", None), entry("g", "/libs/g1", Some("all"))]
+        );
+
+        assert_eq!(PersistedRegistry::diff(&new, &new), RegistryChanges::default());
+    }
+
     #[test]
     fn test_persisted_registry_find_by_token() {
         let mut registry = PersistedRegistry::default();
@@ -222,6 +822,8 @@ mod tests {
             library_path: "/p1/libs".to_string(),
             name: "P1".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
         registry.upsert(ProjectEntry {
             token: "def".to_string(),
@@ -229,6 +831,8 @@ mod tests {
             library_path: "/p2/libs".to_string(),
             name: "P2".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
 
         assert_eq!(registry.find_by_token("abc").unwrap().name, "P1");
@@ -245,6 +849,8 @@ mod tests {
             library_path: "/p1/libs".to_string(),
             name: "P1".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
         registry.remove_by_path("/p1");
         assert!(registry.projects.is_empty());
@@ -259,6 +865,8 @@ mod tests {
             library_path: "/libs/my-lib".to_string(),
             name: "My Lib".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
         registry.upsert(ProjectEntry {
             token: "token2".to_string(),
@@ -266,6 +874,8 @@ mod tests {
             library_path: "/libs/my-lib".to_string(),
             name: "My Lib Updated".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
 
         assert_eq!(registry.projects.len(), 1);
@@ -281,6 +891,8 @@ mod tests {
             library_path: "/libs/my-lib".to_string(),
             name: "My Lib".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
         registry.upsert(ProjectEntry {
             token: "attached".to_string(),
@@ -288,6 +900,8 @@ mod tests {
             library_path: "/libs/my-lib".to_string(),
             name: "My Lib".to_string(),
             description: None,
+            group: None,
+            access: Default::default(),
         });
         registry.remove_by_library_path("/libs/my-lib");
         // Only standalone entry removed, project-attached entry remains
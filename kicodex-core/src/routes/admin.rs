@@ -0,0 +1,81 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+
+use crate::admin::AdminState;
+use crate::models::{RegisterRequest, ReloadRequest, UnregisterRequest};
+use crate::registry::{LibraryStatus, ProjectEntry};
+
+/// GET /internal/status: load status of every token, including load errors.
+pub async fn get_status(State(state): State<AdminState>) -> Json<Vec<LibraryStatus>> {
+    Json(state.registry.status())
+}
+
+/// GET /internal/projects: the registered entries the server has loaded.
+pub async fn get_projects(State(state): State<AdminState>) -> Json<Vec<ProjectEntry>> {
+    Json(state.registry.entries())
+}
+
+/// POST /internal/register: load (or replace) the libraries of the given
+/// entries. Returns the status of their tokens.
+pub async fn post_register(
+    State(state): State<AdminState>,
+    Json(request): Json<RegisterRequest>,
+) -> Result<Json<Vec<LibraryStatus>>, StatusCode> {
+    let registry = state.registry.clone();
+    let tokens: Vec<String> = request.entries.iter().map(|e| e.token.clone()).collect();
+    tokio::task::spawn_blocking(move || registry.load_entries(&request.entries))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(status_of(&state, &tokens)))
+}
+
+/// POST /internal/unregister: stop serving the given tokens.
+pub async fn post_unregister(
+    State(state): State<AdminState>,
+    Json(request): Json<UnregisterRequest>,
+) -> StatusCode {
+    for token in &request.tokens {
+        state.registry.remove(token);
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// POST /internal/reload: reload one token (or all registered ones) from
+/// disk. Returns 404 for a token with no registered entries.
+pub async fn post_reload(
+    State(state): State<AdminState>,
+    Json(request): Json<ReloadRequest>,
+) -> Result<Json<Vec<LibraryStatus>>, StatusCode> {
+    let entries: Vec<ProjectEntry> = state
+        .registry
+        .entries()
+        .into_iter()
+        .filter(|e| request.token.as_ref().is_none_or(|t| *t == e.token))
+        .collect();
+    if request.token.is_some() && entries.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let mut tokens: Vec<String> = entries.iter().map(|e| e.token.clone()).collect();
+    tokens.dedup();
+    let registry = state.registry.clone();
+    tokio::task::spawn_blocking(move || {
+        for entry in &entries {
+            // Failures are recorded in the registry and reported by status
+            let _ = registry.reload(&entry.token, std::path::Path::new(&entry.library_path));
+        }
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(status_of(&state, &tokens)))
+}
+
+fn status_of(state: &AdminState, tokens: &[String]) -> Vec<LibraryStatus> {
+    state
+        .registry
+        .status()
+        .into_iter()
+        .filter(|s| tokens.contains(&s.token))
+        .collect()
+}
@@ -10,12 +10,18 @@ pub async fn get_categories(
     let categories: Vec<Category> = library
         .part_tables
         .iter()
-        .enumerate()
-        .map(|(i, ct)| Category {
-            id: (i + 1).to_string(),
+        .map(|ct| Category {
+            id: ct.id.clone(),
             name: ct.name.clone(),
-            description: String::new(),
+            description: ct.description.clone().unwrap_or_default(),
+            notes: ct.notes.clone(),
         })
+        .chain(library.views.iter().map(|view| Category {
+            id: view.id.clone(),
+            name: view.name.clone(),
+            description: view.description.clone().unwrap_or_default(),
+            notes: view.notes.clone(),
+        }))
         .collect();
     Json(categories)
 }
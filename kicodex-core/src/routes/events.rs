@@ -0,0 +1,53 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::Extension;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::middleware::AuthenticatedToken;
+use crate::models::{LibraryEvent, PartTableRef};
+use crate::registry::{ProjectRegistry, RegistryEvent, RegistryEventKind};
+
+/// GET /v1/events: server-sent events for inserts, reloads and removals of
+/// the authenticated library.
+///
+/// A subscriber that falls too far behind gets a `lagged` event carrying the
+/// number of missed events, and should refetch everything it caches.
+pub async fn get_events(
+    State(registry): State<Arc<ProjectRegistry>>,
+    Extension(AuthenticatedToken(token)): Extension<AuthenticatedToken>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(registry.subscribe()).filter_map(move |event| match event {
+        Ok(event) if event.token == token => Some(Ok(to_sse_event(event))),
+        Ok(_) => None,
+        Err(BroadcastStreamRecvError::Lagged(missed)) => {
+            Some(Ok(Event::default().event("lagged").data(missed.to_string())))
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn to_sse_event(event: RegistryEvent) -> Event {
+    let name = event.kind.name();
+    let (changed_tables, error) = match event.kind {
+        RegistryEventKind::Reloaded { changed_tables } => (changed_tables, None),
+        RegistryEventKind::ReloadFailed { error } => (Vec::new(), Some(error)),
+        RegistryEventKind::Inserted | RegistryEventKind::Removed => (Vec::new(), None),
+    };
+    let data = LibraryEvent {
+        library: event.library,
+        changed_tables: changed_tables
+            .into_iter()
+            .map(|(id, name)| PartTableRef { id, name })
+            .collect(),
+        error,
+    };
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|_| Event::default().event(name))
+}
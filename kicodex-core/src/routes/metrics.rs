@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+
+use crate::registry::ProjectRegistry;
+
+pub async fn get_metrics(State(registry): State<Arc<ProjectRegistry>>) -> impl IntoResponse {
+    let body = registry
+        .metrics()
+        .render(&registry.status(), &registry.token_names());
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        body,
+    )
+}
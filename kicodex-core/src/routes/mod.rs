@@ -1,3 +1,6 @@
+pub mod admin;
 pub mod categories;
+pub mod events;
+pub mod metrics;
 pub mod parts;
 pub mod root;
@@ -3,30 +3,29 @@ use axum::http::StatusCode;
 use axum::{Extension, Json};
 use indexmap::IndexMap;
 
-use crate::data::schema::ResolvedSchema;
+use crate::data::lifecycle::Lifecycle;
+use crate::data::schema::{self, ResolvedSchema};
+use crate::metrics::PartsServed;
 use crate::middleware::AuthenticatedLibrary;
 use crate::models::{FieldValue, PartDetail, PartSummary};
 
 pub async fn get_parts_by_category(
     Extension(AuthenticatedLibrary(library)): Extension<AuthenticatedLibrary>,
     Path(category_id): Path<String>,
-) -> Result<Json<Vec<PartSummary>>, StatusCode> {
+) -> Result<(Extension<PartsServed>, Json<Vec<PartSummary>>), StatusCode> {
     let category_id = category_id.strip_suffix(".json").unwrap_or(&category_id);
-    let idx: usize = category_id
-        .parse::<usize>()
-        .map_err(|_| StatusCode::NOT_FOUND)?
-        .checked_sub(1)
+    let rows = library
+        .category_parts(category_id)
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    let ct = library.part_tables.get(idx).ok_or(StatusCode::NOT_FOUND)?;
-
-    let parts: Vec<PartSummary> = ct
-        .components
-        .iter()
-        .map(|row| {
+    // Non-active parts are hidden from listings but still resolve by id
+    let parts: Vec<PartSummary> = rows
+        .into_iter()
+        .filter(|(_, row)| Lifecycle::of_row(row).is_active())
+        .map(|(ct, row)| {
             let id = row.get("id").cloned().unwrap_or_default();
-            let name = display_name_from_row(row);
-            let description = row.get("description").cloned().unwrap_or_default();
+            let name = part_name(row, &ct.template);
+            let description = part_description(row, &ct.template);
             PartSummary {
                 id,
                 name,
@@ -35,26 +34,19 @@ pub async fn get_parts_by_category(
         })
         .collect();
 
-    Ok(Json(parts))
+    Ok((Extension(PartsServed(parts.len())), Json(parts)))
 }
 
 pub async fn get_part_detail(
     Extension(AuthenticatedLibrary(library)): Extension<AuthenticatedLibrary>,
     Path(part_id): Path<String>,
-) -> Result<Json<PartDetail>, StatusCode> {
+) -> Result<(Extension<PartsServed>, Json<PartDetail>), StatusCode> {
     let part_id = part_id.strip_suffix(".json").unwrap_or(&part_id);
-    // Search all part tables for the part
-    for ct in &library.part_tables {
-        if let Some(row) = ct
-            .components
-            .iter()
-            .find(|r| r.get("id").map(|s| s.as_str()) == Some(part_id))
-        {
-            return Ok(Json(build_part_detail(row, &ct.template)));
-        }
-    }
-
-    Err(StatusCode::NOT_FOUND)
+    let (ct, row) = library.find_part(part_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok((
+        Extension(PartsServed(1)),
+        Json(build_part_detail(row, &ct.template)),
+    ))
 }
 
 /// Columns that should not be used as a fallback display name.
@@ -71,17 +63,22 @@ const NON_NAME_COLUMNS: &[&str] = &[
 ///
 /// Preference order: `mpn` → `value` → first other non-special non-empty field → `id`.
 /// Empty string values are skipped so that an unpopulated `mpn` column doesn't
-/// shadow a populated `value` column.
-fn display_name_from_row(row: &indexmap::IndexMap<String, String>) -> String {
+/// shadow a populated `value` column, and internal columns (see
+/// `ResolvedSchema::is_exported`) are never used.
+fn display_name_from_row(row: &IndexMap<String, String>, schema: &ResolvedSchema) -> String {
     // Prefer mpn, then value (skip empties)
     for key in &["mpn", "value"] {
-        if let Some(v) = row.get(*key).filter(|s| !s.is_empty()) {
+        if let Some(v) = row
+            .get(*key)
+            .filter(|s| !s.is_empty() && schema.is_exported(key))
+        {
             return v.clone();
         }
     }
-    // Fallback: first non-special column with a non-empty value
+    // Fallback: first non-special exported column with a non-empty value
     for (k, v) in row {
         if !NON_NAME_COLUMNS.contains(&k.as_str())
+            && schema.is_exported(k)
             && k != "mpn"
             && k != "value"
             && !v.is_empty()
@@ -93,6 +90,41 @@ fn display_name_from_row(row: &indexmap::IndexMap<String, String>) -> String {
     row.get("id").cloned().unwrap_or_default()
 }
 
+/// Render an optional template format from the row's exported columns,
+/// treating an empty result as absent.
+fn render_optional(
+    format: Option<&str>,
+    row: &IndexMap<String, String>,
+    schema: &ResolvedSchema,
+) -> Option<String> {
+    format
+        .map(|f| {
+            schema::render_format_with(f, |name| {
+                row.get(name)
+                    .filter(|_| schema.is_exported(name))
+                    .map(String::as_str)
+            })
+        })
+        .filter(|s| !s.is_empty())
+}
+
+/// Part name: the template's `name_format` if set, otherwise `display_name_from_row`.
+fn part_name(row: &IndexMap<String, String>, schema: &ResolvedSchema) -> String {
+    render_optional(schema.name_format.as_deref(), row, schema)
+        .unwrap_or_else(|| display_name_from_row(row, schema))
+}
+
+/// Part summary description: the template's `description_format` if set,
+/// otherwise the raw `description` column when it is exported.
+fn part_description(row: &IndexMap<String, String>, schema: &ResolvedSchema) -> String {
+    render_optional(schema.description_format.as_deref(), row, schema).unwrap_or_else(|| {
+        row.get("description")
+            .filter(|_| schema.is_exported("description"))
+            .cloned()
+            .unwrap_or_default()
+    })
+}
+
 /// Special CSV columns that map to top-level API fields (not included in `fields` dict).
 const TOP_LEVEL_COLUMNS: &[&str] = &[
     "id",
@@ -151,13 +183,13 @@ fn exclude_flag(row: &IndexMap<String, String>, field: &str, schema_default: boo
 
 fn build_part_detail(row: &IndexMap<String, String>, schema: &ResolvedSchema) -> PartDetail {
     let id = row.get("id").cloned().unwrap_or_default();
-    let name = display_name_from_row(row);
+    let name = part_name(row, schema);
     let symbol_id_str = row.get("symbol").cloned().unwrap_or_default();
 
     let mut fields = IndexMap::new();
 
     for (key, value) in row {
-        if TOP_LEVEL_COLUMNS.contains(&key.as_str()) {
+        if TOP_LEVEL_COLUMNS.contains(&key.as_str()) || !schema.is_exported(key) {
             continue;
         }
 
@@ -1,14 +1,23 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use axum::Router;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tower_http::trace::TraceLayer;
 
+use crate::config::{ServerConfig, TlsConfig};
+use crate::data::computed;
 use crate::data::csv_loader::{self, CsvRow};
+use crate::data::filter::{self, Filter};
 use crate::data::library::{self, LibraryManifest};
+use crate::data::lifecycle;
+use crate::data::project::PartFilter;
 use crate::data::schema;
-use crate::middleware;
+use crate::middleware::{self, AccessPolicy};
 use crate::registry::ProjectRegistry;
 use crate::routes;
 
@@ -20,56 +29,670 @@ pub enum ServerError {
     Schema(#[from] schema::SchemaError),
     #[error("CSV error: {0}")]
     Csv(#[from] csv_loader::CsvError),
+    #[error("view '{view}' has an invalid filter: {source}")]
+    ViewFilter {
+        view: String,
+        source: filter::FilterError,
+    },
+    #[error("view '{view}' references unknown part table '{table}'")]
+    ViewPartTable { view: String, table: String },
+    #[error("invalid project part filter: {0}")]
+    PartFilter(filter::FilterError),
     #[error("server error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("config error: {0}")]
+    Config(#[from] crate::config::ConfigError),
+    #[error("failed to load TLS certificate: {0}")]
+    Tls(std::io::Error),
 }
 
 /// A loaded part table with its data and metadata.
 #[derive(Debug, Clone)]
 pub struct LoadedPartTable {
+    /// Stable category ID from library.yaml.
+    pub id: String,
     pub name: String,
+    /// Category description: from library.yaml, else from the template.
+    pub description: Option<String>,
+    pub notes: Option<String>,
     pub template_name: String,
     pub components: Vec<CsvRow>,
     pub template: schema::ResolvedSchema,
 }
 
+/// A virtual category: rows from one or more part tables matching a filter.
+#[derive(Debug, Clone)]
+pub struct LoadedView {
+    /// Stable category ID from library.yaml.
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub notes: Option<String>,
+    /// Matching rows as (part table index, row index).
+    pub parts: Vec<(usize, usize)>,
+}
+
 /// The full loaded library state.
 #[derive(Debug, Clone)]
 pub struct LoadedLibrary {
     pub name: String,
     pub description: Option<String>,
     pub part_tables: Vec<LoadedPartTable>,
+    pub views: Vec<LoadedView>,
+    /// Part ID → (part table index, row index), built once at load time.
+    pub part_index: HashMap<String, (usize, usize)>,
+    /// Hash of everything served from this library, used as its ETag.
+    pub content_hash: u64,
+    /// When the served content last changed, used as its Last-Modified date.
+    /// The registry keeps the previous time when a reload changes nothing.
+    pub modified: SystemTime,
+    /// Set on the placeholder served in place of a library that failed to
+    /// load (see `load_error_library`).
+    pub load_error: Option<String>,
+    /// Part tables left out because they failed to load. The other tables
+    /// are still served.
+    pub diagnostics: Vec<LoadDiagnostic>,
+}
+
+/// A part table that failed to load and was left out of its library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadDiagnostic {
+    pub part_table: String,
+    pub error: String,
+}
+
+impl LoadedLibrary {
+    /// Look up a part by ID across all part tables.
+    pub fn find_part(&self, part_id: &str) -> Option<(&LoadedPartTable, &CsvRow)> {
+        let &(table_idx, row_idx) = self.part_index.get(part_id)?;
+        let ct = self.part_tables.get(table_idx)?;
+        let row = ct.components.get(row_idx)?;
+        Some((ct, row))
+    }
+
+    /// All rows of a category, which is either a part table or a view.
+    pub fn category_parts(&self, category_id: &str) -> Option<Vec<(&LoadedPartTable, &CsvRow)>> {
+        if let Some(ct) = self.part_tables.iter().find(|ct| ct.id == category_id) {
+            return Some(ct.components.iter().map(|row| (ct, row)).collect());
+        }
+        let view = self.views.iter().find(|v| v.id == category_id)?;
+        Some(
+            view.parts
+                .iter()
+                .filter_map(|&(table_idx, row_idx)| {
+                    let ct = self.part_tables.get(table_idx)?;
+                    Some((ct, ct.components.get(row_idx)?))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Build the part ID index. When the same ID appears in several part tables,
+/// the first table wins.
+fn build_part_index(part_tables: &[LoadedPartTable]) -> HashMap<String, (usize, usize)> {
+    let mut index = HashMap::new();
+    for (table_idx, ct) in part_tables.iter().enumerate() {
+        for (row_idx, row) in ct.components.iter().enumerate() {
+            if let Some(id) = row.get("id").filter(|id| !id.is_empty()) {
+                index.entry(id.clone()).or_insert((table_idx, row_idx));
+            }
+        }
+    }
+    index
+}
+
+/// Hash the served content of a library: names, part tables with their
+/// rows and output-affecting template settings, and views.
+fn content_hash(
+    name: &str,
+    description: Option<&str>,
+    part_tables: &[LoadedPartTable],
+    views: &[LoadedView],
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    description.hash(&mut hasher);
+    for ct in part_tables {
+        hash_part_table(ct, &mut hasher);
+    }
+    for view in views {
+        view.id.hash(&mut hasher);
+        view.name.hash(&mut hasher);
+        view.description.hash(&mut hasher);
+        view.notes.hash(&mut hasher);
+        view.parts.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn hash_part_table(ct: &LoadedPartTable, hasher: &mut impl Hasher) {
+    ct.id.hash(hasher);
+    ct.name.hash(hasher);
+    ct.description.hash(hasher);
+    ct.notes.hash(hasher);
+    ct.template_name.hash(hasher);
+    let template = &ct.template;
+    template.name_format.hash(hasher);
+    template.description_format.hash(hasher);
+    template.exclude_from_bom.hash(hasher);
+    template.exclude_from_board.hash(hasher);
+    template.exclude_from_sim.hash(hasher);
+    for field in &template.fields {
+        field.hash(hasher);
+    }
+    ct.components.len().hash(hasher);
+    for row in &ct.components {
+        row.len().hash(hasher);
+        for column in row {
+            column.hash(hasher);
+        }
+    }
+}
+
+/// Part tables that differ between two versions of a library, as
+/// (category ID, name). Tables that were added or removed count as changed.
+pub fn changed_part_tables(old: &LoadedLibrary, new: &LoadedLibrary) -> Vec<(String, String)> {
+    let table_hash = |ct: &LoadedPartTable| {
+        let mut hasher = DefaultHasher::new();
+        hash_part_table(ct, &mut hasher);
+        hasher.finish()
+    };
+    let old_hashes: HashMap<&str, u64> = old
+        .part_tables
+        .iter()
+        .map(|ct| (ct.id.as_str(), table_hash(ct)))
+        .collect();
+
+    let mut changed: Vec<(String, String)> = new
+        .part_tables
+        .iter()
+        .filter(|ct| old_hashes.get(ct.id.as_str()) != Some(&table_hash(ct)))
+        .map(|ct| (ct.id.clone(), ct.name.clone()))
+        .collect();
+    changed.extend(
+        old.part_tables
+            .iter()
+            .filter(|ct| !new.part_tables.iter().any(|n| n.id == ct.id))
+            .map(|ct| (ct.id.clone(), ct.name.clone())),
+    );
+    changed
 }
 
 /// Load a library from disk into memory.
 pub fn load_library(library_root: &Path) -> Result<LoadedLibrary, ServerError> {
-    let manifest: LibraryManifest = library::load_library_manifest(library_root)?;
+    load_library_filtered(library_root, &PartFilter::default())
+}
+
+/// Load a library, keeping only the part tables and rows that a project's
+/// `PartFilter` allows. Filtered-out parts are neither listed nor resolvable.
+pub fn load_library_filtered(
+    library_root: &Path,
+    part_filter: &PartFilter,
+) -> Result<LoadedLibrary, ServerError> {
+    load_library_with_mode(library_root, part_filter, LoadMode::default())
+}
+
+/// Whether loading a library may write to its files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Missing and duplicate IDs are assigned and written back to
+    /// library.yaml and the CSV files.
+    #[default]
+    WriteBack,
+    /// Library files are never written, for read-only checkouts and mounts.
+    /// Missing IDs are assigned in memory (row IDs are derived from the row
+    /// content, so they are stable across loads) and duplicate row IDs are
+    /// only reported.
+    ReadOnly,
+}
+
+/// Load a library with a project's `PartFilter` in the given mode.
+pub fn load_library_with_mode(
+    library_root: &Path,
+    part_filter: &PartFilter,
+    mode: LoadMode,
+) -> Result<LoadedLibrary, ServerError> {
+    load_library_reusing(library_root, part_filter, mode, None).map(|(library, _)| library)
+}
+
+/// Reload a library after files covered by `scope` changed. When `scope`
+/// names part tables, only those are read from disk again and the others are
+/// reused from `previous` as they are.
+pub fn reload_library(
+    library_root: &Path,
+    part_filter: &PartFilter,
+    mode: LoadMode,
+    previous: Option<&LoadedLibrary>,
+    scope: &ReloadScope,
+) -> Result<(LoadedLibrary, ReloadStats), ServerError> {
+    let reuse = match (previous, scope) {
+        (Some(previous), ReloadScope::PartTables(tables)) if previous.load_error.is_none() => {
+            Some((previous, tables.as_slice()))
+        }
+        (Some(previous), ReloadScope::Unaffected) if previous.load_error.is_none() => {
+            Some((previous, [].as_slice()))
+        }
+        _ => None,
+    };
+    load_library_reusing(library_root, part_filter, mode, reuse)
+}
+
+/// Load a library. With `reuse`, part tables of the previous version that
+/// are not in the given list of table names are kept instead of reloaded.
+fn load_library_reusing(
+    library_root: &Path,
+    part_filter: &PartFilter,
+    mode: LoadMode,
+    reuse: Option<(&LoadedLibrary, &[String])>,
+) -> Result<(LoadedLibrary, ReloadStats), ServerError> {
+    let manifest: LibraryManifest = match mode {
+        LoadMode::WriteBack => library::load_library_manifest_with_ids(library_root)?,
+        LoadMode::ReadOnly => {
+            let mut manifest = library::load_library_manifest(library_root)?;
+            library::assign_part_table_ids(&mut manifest);
+            library::assign_view_ids(&mut manifest);
+            manifest
+        }
+    };
     let schemas_dir = library_root.join(&manifest.templates_path);
+    let row_filter = part_filter
+        .row_filter
+        .as_deref()
+        .map(Filter::parse)
+        .transpose()
+        .map_err(ServerError::PartFilter)?;
 
+    let mut stats = ReloadStats::default();
     let mut part_tables = Vec::new();
+    let mut failures = Vec::new();
     for ct_def in &manifest.part_tables {
-        let resolved = schema::load_schema(&schemas_dir, &ct_def.template)?;
-        let csv_path = library_root.join(&ct_def.file);
-        let components = csv_loader::load_csv_with_ids(&csv_path)?;
-
-        part_tables.push(LoadedPartTable {
-            name: ct_def.name.clone(),
-            template_name: ct_def.template.clone(),
-            components,
-            template: resolved,
+        if !part_filter.includes_table(&ct_def.name, &ct_def.template) {
+            continue;
+        }
+        let reused = reuse.and_then(|(previous, changed)| {
+            if changed.contains(&ct_def.name) {
+                return None;
+            }
+            previous.part_tables.iter().find(|ct| {
+                ct.name == ct_def.name
+                    && ct.template_name == ct_def.template
+                    && ct_def.id.as_ref() == Some(&ct.id)
+            })
         });
+        if let Some(ct) = reused {
+            part_tables.push(ct.clone());
+            stats.part_tables_reused += 1;
+            continue;
+        }
+        match load_part_table(library_root, &schemas_dir, ct_def, row_filter.as_ref(), mode) {
+            Ok(ct) => {
+                stats.part_tables_reloaded += 1;
+                stats.parts_reloaded += ct.components.len();
+                part_tables.push(ct);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Skipping part table '{}' of library '{}': {}",
+                    ct_def.name,
+                    manifest.name,
+                    e
+                );
+                failures.push((ct_def.name.clone(), e));
+            }
+        }
     }
+    // Nothing left to serve: fail the library as a whole
+    if part_tables.is_empty() && !failures.is_empty() {
+        return Err(failures.swap_remove(0).1);
+    }
+    let diagnostics = failures
+        .into_iter()
+        .map(|(part_table, e)| LoadDiagnostic {
+            part_table,
+            error: e.to_string(),
+        })
+        .collect();
+
+    let mut views = Vec::new();
+    for view_def in &manifest.views {
+        if let Some(view) = load_view(view_def, &manifest.part_tables, &part_tables)? {
+            views.push(view);
+        }
+    }
+    let part_index = build_part_index(&part_tables);
+    let content_hash = content_hash(
+        &manifest.name,
+        manifest.description.as_deref(),
+        &part_tables,
+        &views,
+    );
 
-    Ok(LoadedLibrary {
+    let library = LoadedLibrary {
         name: manifest.name,
         description: manifest.description,
         part_tables,
+        views,
+        part_index,
+        content_hash,
+        modified: SystemTime::now(),
+        load_error: None,
+        diagnostics,
+    };
+    Ok((library, stats))
+}
+
+/// What changed files inside a library mean for its loaded part tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReloadScope {
+    /// No file a part table is loaded from changed.
+    Unaffected,
+    /// Only these part tables (by name) have to be loaded again.
+    PartTables(Vec<String>),
+    /// library.yaml changed, or the change could not be narrowed down.
+    Full,
+}
+
+/// Part tables reloaded and reused by a reload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReloadStats {
+    pub part_tables_reloaded: usize,
+    pub part_tables_reused: usize,
+    /// Rows of the reloaded part tables.
+    pub parts_reloaded: usize,
+}
+
+/// Work out which part tables depend on the `changed` files under
+/// `library_root`: a table depends on its CSV file, on every template in its
+/// inheritance chain and on `_base.yaml`. Hidden files and temp files (such as the ones
+/// written while saving a CSV) are ignored.
+pub fn reload_scope(library_root: &Path, changed: &[PathBuf]) -> ReloadScope {
+    let relative: Vec<PathBuf> = changed
+        .iter()
+        .filter_map(|path| relative_path(library_root, path))
+        .filter(|path| !is_ignored(path))
+        .collect();
+    if relative.is_empty() {
+        return ReloadScope::Unaffected;
+    }
+    if relative.iter().any(|path| path == Path::new("library.yaml")) {
+        return ReloadScope::Full;
+    }
+    let Ok(manifest) = library::load_library_manifest(library_root) else {
+        return ReloadScope::Full;
+    };
+
+    let templates_dir = normalize(Path::new(&manifest.templates_path));
+    let changed_templates: Vec<&str> = relative
+        .iter()
+        .filter(|path| path.parent() == Some(templates_dir.as_path()))
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("yaml")))
+        .filter_map(|path| path.file_stem()?.to_str())
+        .collect();
+    let schemas_dir = library_root.join(&manifest.templates_path);
+
+    let tables: Vec<String> = manifest
+        .part_tables
+        .iter()
+        .filter(|ct_def| {
+            // Every template load parses the base template
+            relative.contains(&normalize(Path::new(&ct_def.file)))
+                || changed_templates.contains(&"_base")
+                || (!changed_templates.is_empty()
+                    && schema::template_chain(&schemas_dir, &ct_def.template)
+                        .iter()
+                        .any(|t| changed_templates.contains(&t.as_str())))
+        })
+        .map(|ct_def| ct_def.name.clone())
+        .collect();
+    if tables.is_empty() {
+        ReloadScope::Unaffected
+    } else {
+        ReloadScope::PartTables(tables)
+    }
+}
+
+/// `path` relative to `root`, also when only one of them is canonical.
+fn relative_path(root: &Path, path: &Path) -> Option<PathBuf> {
+    if let Ok(relative) = path.strip_prefix(root) {
+        return Some(normalize(relative));
+    }
+    // The file itself may be gone, so canonicalize its directory
+    let root = root.canonicalize().ok()?;
+    let dir = path.parent()?.canonicalize().ok()?;
+    let relative = dir.join(path.file_name()?);
+    Some(normalize(relative.strip_prefix(&root).ok()?))
+}
+
+/// Drop `.` components so `./parts.csv` and `parts.csv` compare equal.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+/// Hidden files and directories, and temp files, are never library inputs.
+fn is_ignored(path: &Path) -> bool {
+    path.components().any(|c| {
+        c.as_os_str().to_str().is_some_and(|name| name.starts_with('.'))
+    }) || path.extension().is_some_and(|ext| ext == "tmp")
+}
+
+/// Load one part table of a library, with its template and computed fields.
+fn load_part_table(
+    library_root: &Path,
+    schemas_dir: &Path,
+    ct_def: &library::PartTableDef,
+    row_filter: Option<&Filter>,
+    mode: LoadMode,
+) -> Result<LoadedPartTable, ServerError> {
+    let resolved = schema::load_schema(schemas_dir, &ct_def.template)?;
+    let csv_path = library_root.join(&ct_def.file);
+    let mut components = match mode {
+        LoadMode::WriteBack => csv_loader::load_csv_with_ids(&csv_path)?,
+        // Keyed by file rather than name, so renaming the table keeps its IDs
+        LoadMode::ReadOnly => csv_loader::load_csv_read_only(&csv_path, &ct_def.file)?,
+    };
+    computed::apply_computed_fields(&resolved, &mut components);
+    if let Some(row_filter) = row_filter {
+        components.retain(|row| row_filter.matches(row));
+    }
+
+    Ok(LoadedPartTable {
+        id: ct_def.id.clone().unwrap_or_default(),
+        name: ct_def.name.clone(),
+        description: ct_def
+            .description
+            .clone()
+            .or_else(|| resolved.description.clone()),
+        notes: ct_def.notes.clone(),
+        template_name: ct_def.template.clone(),
+        components,
+        template: resolved,
     })
 }
 
+/// Merge several libraries into one, served under a single token.
+///
+/// Category names are prefixed with the member name (`passives / Resistors`)
+/// and category and part IDs are namespaced as `member:id`, so members never
+/// collide.
+pub fn merge_libraries(name: &str, members: &[(&str, &LoadedLibrary)]) -> LoadedLibrary {
+    let mut part_tables = Vec::new();
+    let mut views = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for &(member, library) in members {
+        let namespaced = |id: &str| format!("{}:{}", member, id);
+        let table_offset = part_tables.len();
+
+        for ct in &library.part_tables {
+            let components = ct
+                .components
+                .iter()
+                .map(|row| {
+                    let mut row = row.clone();
+                    for column in ["id", lifecycle::REPLACED_BY_COLUMN] {
+                        if let Some(value) = row.get_mut(column).filter(|v| !v.is_empty()) {
+                            *value = namespaced(value);
+                        }
+                    }
+                    row
+                })
+                .collect();
+            part_tables.push(LoadedPartTable {
+                id: namespaced(&ct.id),
+                name: format!("{} / {}", member, ct.name),
+                description: ct.description.clone(),
+                notes: ct.notes.clone(),
+                template_name: ct.template_name.clone(),
+                components,
+                template: ct.template.clone(),
+            });
+        }
+
+        for view in &library.views {
+            views.push(LoadedView {
+                id: namespaced(&view.id),
+                name: format!("{} / {}", member, view.name),
+                description: view.description.clone(),
+                notes: view.notes.clone(),
+                parts: view
+                    .parts
+                    .iter()
+                    .map(|&(table_idx, row_idx)| (table_idx + table_offset, row_idx))
+                    .collect(),
+            });
+        }
+
+        diagnostics.extend(library.diagnostics.iter().map(|d| LoadDiagnostic {
+            part_table: format!("{} / {}", member, d.part_table),
+            error: d.error.clone(),
+        }));
+    }
+
+    let part_index = build_part_index(&part_tables);
+    let content_hash = content_hash(name, None, &part_tables, &views);
+
+    LoadedLibrary {
+        name: name.to_string(),
+        description: None,
+        part_tables,
+        views,
+        part_index,
+        content_hash,
+        modified: SystemTime::now(),
+        load_error: None,
+        diagnostics,
+    }
+}
+
+/// Category ID of the placeholder served for a library that failed to load.
+pub const LOAD_ERROR_CATEGORY_ID: &str = "load-error";
+
+/// A placeholder for a library that failed to load: a single empty category
+/// whose name and description carry the error, so it shows up in KiCad's
+/// symbol chooser instead of the library silently going missing.
+pub fn load_error_library(name: &str, error: &str) -> LoadedLibrary {
+    let summary = error.lines().next().unwrap_or_default();
+    let part_tables = vec![LoadedPartTable {
+        id: LOAD_ERROR_CATEGORY_ID.to_string(),
+        name: format!("Library failed to load: {summary}"),
+        description: Some(format!("Library '{name}' failed to load: {error}")),
+        notes: None,
+        template_name: String::new(),
+        components: Vec::new(),
+        template: schema::ResolvedSchema::default(),
+    }];
+    let description = Some(format!("Failed to load: {summary}"));
+    let content_hash = content_hash(name, description.as_deref(), &part_tables, &[]);
+
+    LoadedLibrary {
+        name: name.to_string(),
+        description,
+        part_tables,
+        views: Vec::new(),
+        part_index: HashMap::new(),
+        content_hash,
+        modified: SystemTime::now(),
+        load_error: Some(error.to_string()),
+        diagnostics: Vec::new(),
+    }
+}
+
+/// Resolve a view's filter against the loaded part tables.
+///
+/// Tables left out by a project's `PartFilter` are skipped; a view whose
+/// tables were all left out is dropped (`None`).
+fn load_view(
+    view_def: &library::ViewDef,
+    table_defs: &[library::PartTableDef],
+    part_tables: &[LoadedPartTable],
+) -> Result<Option<LoadedView>, ServerError> {
+    let filter = Filter::parse(&view_def.filter).map_err(|source| ServerError::ViewFilter {
+        view: view_def.name.clone(),
+        source,
+    })?;
+
+    let mut table_indices = Vec::new();
+    if view_def.part_tables.is_empty() {
+        table_indices.extend(0..part_tables.len());
+    } else {
+        for table in &view_def.part_tables {
+            if let Some(idx) = part_tables
+                .iter()
+                .position(|ct| &ct.name == table || &ct.template_name == table)
+            {
+                table_indices.push(idx);
+            } else if !table_defs
+                .iter()
+                .any(|t| &t.name == table || &t.template == table)
+            {
+                return Err(ServerError::ViewPartTable {
+                    view: view_def.name.clone(),
+                    table: table.clone(),
+                });
+            }
+        }
+        if table_indices.is_empty() {
+            return Ok(None);
+        }
+    }
+
+    let parts = table_indices
+        .into_iter()
+        .flat_map(|table_idx| {
+            part_tables[table_idx]
+                .components
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| filter.matches(row))
+                .map(move |(row_idx, _)| (table_idx, row_idx))
+        })
+        .collect();
+
+    Ok(Some(LoadedView {
+        id: view_def.id.clone().unwrap_or_default(),
+        name: view_def.name.clone(),
+        description: view_def.description.clone(),
+        notes: view_def.notes.clone(),
+        parts,
+    }))
+}
+
 /// Build the Axum router with auth middleware and all routes.
 pub fn build_router(registry: Arc<ProjectRegistry>) -> Router {
-    Router::new()
+    build_router_with_policy(registry, AccessPolicy::default())
+}
+
+/// Build the Axum router, enforcing an access policy.
+pub fn build_router_with_policy(registry: Arc<ProjectRegistry>, policy: AccessPolicy) -> Router {
+    let policy = Arc::new(policy);
+    let auth_state = middleware::AuthState {
+        registry: registry.clone(),
+        policy: policy.clone(),
+    };
+    let router = Router::new()
         .route("/v1/", axum::routing::get(routes::root::get_root))
         .route(
             "/v1/categories.json",
@@ -83,16 +706,48 @@ pub fn build_router(registry: Arc<ProjectRegistry>) -> Router {
             "/v1/parts/{partId}",
             axum::routing::get(routes::parts::get_part_detail),
         )
+        .layer(axum::middleware::from_fn(middleware::conditional_get_middleware))
+        // The event stream is never cacheable, so it sits outside the ETag layer
+        .route("/v1/events", axum::routing::get(routes::events::get_events))
         .layer(axum::middleware::from_fn_with_state(
-            registry.clone(),
+            auth_state,
             middleware::auth_middleware,
         ))
-        .layer(TraceLayer::new_for_http())
+        // Outside auth, so rejected requests are counted as well
+        .layer(axum::middleware::from_fn_with_state(
+            registry.clone(),
+            middleware::metrics_middleware,
+        ))
+        .with_state(registry);
+    restrict_hosts(router, &policy).layer(TraceLayer::new_for_http())
+}
+
+/// Router serving the registry's metrics at `/metrics`, without token auth.
+pub fn metrics_router(registry: Arc<ProjectRegistry>) -> Router {
+    Router::new()
+        .route("/metrics", axum::routing::get(routes::metrics::get_metrics))
         .with_state(registry)
+        .layer(TraceLayer::new_for_http())
+}
+
+/// Reject requests for hosts outside the policy's allowlist.
+fn restrict_hosts(router: Router, policy: &Arc<AccessPolicy>) -> Router {
+    if policy.allowed_hosts.is_empty() {
+        return router;
+    }
+    router.layer(axum::middleware::from_fn_with_state(
+        policy.clone(),
+        middleware::host_allowlist_middleware,
+    ))
 }
 
 /// Start the server in single-library mode (Phase 1 compatible).
-pub async fn run_server(library_root: &Path, port: u16, host: &str) -> Result<(), ServerError> {
+pub async fn run_server(
+    library_root: &Path,
+    port: u16,
+    host: &str,
+    policy: AccessPolicy,
+) -> Result<(), ServerError> {
     let library = load_library(library_root)?;
     tracing::info!(
         "Loaded library '{}' with {} part table(s)",
@@ -105,26 +760,87 @@ pub async fn run_server(library_root: &Path, port: u16, host: &str) -> Result<()
 
     let registry = ProjectRegistry::new();
     let token = uuid::Uuid::new_v4().to_string();
+    if policy.strict {
+        tracing::info!("Strict mode: clients must send 'Authorization: Token {}'", token);
+    }
     registry.insert(&token, library);
 
-    let app = build_router(Arc::new(registry));
-    serve_on(app, host, port).await
+    run_server_with_policy(Arc::new(registry), port, host, policy).await
 }
 
-/// Start the server with a pre-built registry (multi-project mode).
+/// Start the server with a pre-built registry (multi-project mode), with the
+/// admin API mounted under `/internal/`.
 pub async fn run_server_with_registry(
     registry: Arc<ProjectRegistry>,
     port: u16,
     host: &str,
 ) -> Result<(), ServerError> {
-    let app = build_router(registry);
-    serve_on(app, host, port).await
+    run_server_with_policy(registry, port, host, AccessPolicy::default()).await
 }
 
-async fn serve_on(app: Router, host: &str, port: u16) -> Result<(), ServerError> {
+/// Like `run_server_with_registry`, enforcing an access policy on both the
+/// library and the admin routes.
+pub async fn run_server_with_policy(
+    registry: Arc<ProjectRegistry>,
+    port: u16,
+    host: &str,
+    policy: AccessPolicy,
+) -> Result<(), ServerError> {
+    let config = ServerConfig::load_default()?;
+    let policy = Arc::new(policy);
+    let mut app = build_router_with_policy(registry.clone(), (*policy).clone());
+    if config.metrics {
+        tracing::info!("Serving metrics at /metrics");
+        app = app.merge(restrict_hosts(metrics_router(registry.clone()), &policy));
+    }
+    match crate::admin::admin_token_path().map(|p| crate::admin::load_or_create_admin_token(&p)) {
+        Some(Ok(token)) => {
+            let admin = crate::admin::admin_router(registry, &token);
+            app = app.merge(restrict_hosts(admin, &policy));
+        }
+        Some(Err(e)) => tracing::warn!("Admin API disabled, failed to set up its token: {}", e),
+        None => tracing::warn!("Admin API disabled, could not determine config directory"),
+    }
+    serve_on(app, host, port, config.tls.as_ref()).await
+}
+
+/// Serve `app` on `host:port`, over HTTPS when `tls` is set.
+async fn serve_on(
+    app: Router,
+    host: &str,
+    port: u16,
+    tls: Option<&TlsConfig>,
+) -> Result<(), ServerError> {
     let addr = format!("{host}:{port}");
-    tracing::info!("Starting KiCodex server on http://{addr}");
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    match tls {
+        Some(tls) => {
+            tracing::info!("Starting KiCodex server on https://{addr}");
+            let listener = std::net::TcpListener::bind(&addr)?;
+            serve_tls(listener, app, tls).await
+        }
+        None => {
+            tracing::info!("Starting KiCodex server on http://{addr}");
+            let listener = tokio::net::TcpListener::bind(&addr).await?;
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            )
+            .await?;
+            Ok(())
+        }
+    }
+}
+
+/// Serve `app` over HTTPS on an already bound listener.
+pub async fn serve_tls(
+    listener: std::net::TcpListener,
+    app: Router,
+    tls: &TlsConfig,
+) -> Result<(), ServerError> {
+    let config = crate::tls::rustls_config(tls).await.map_err(ServerError::Tls)?;
+    listener.set_nonblocking(true)?;
+    axum_server::from_tcp_rustls(listener, config)
+        .serve(app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .await?;
     Ok(())
 }
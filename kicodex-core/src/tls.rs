@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::config::TlsConfig;
+
+/// Names every generated certificate is valid for, matching the root URL
+/// written to `.kicad_httplib` files.
+pub const DEFAULT_CERT_NAMES: &[&str] = &["localhost", "127.0.0.1", "::1"];
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("failed to generate certificate: {0}")]
+    Generate(#[from] rcgen::Error),
+    #[error("failed to write certificate: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A freshly generated certificate and its private key, PEM encoded.
+pub struct SelfSignedCert {
+    pub cert_pem: String,
+    pub key_pem: String,
+}
+
+impl SelfSignedCert {
+    /// Write the certificate and key to the paths of `tls`. The key file is
+    /// only readable by the current user.
+    pub fn write(&self, tls: &TlsConfig) -> Result<(), TlsError> {
+        write_file(&tls.cert_path, &self.cert_pem)?;
+        write_file(&tls.key_path, &self.key_pem)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tls.key_path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+}
+
+/// Install the process-wide rustls crypto provider. Safe to call repeatedly.
+pub fn install_crypto_provider() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+}
+
+/// Generate a self-signed certificate for the default local names plus
+/// `extra_names` (DNS names or IP addresses).
+pub fn generate_self_signed(extra_names: &[String]) -> Result<SelfSignedCert, TlsError> {
+    let mut names: Vec<String> = DEFAULT_CERT_NAMES.iter().map(|n| n.to_string()).collect();
+    for name in extra_names {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    let certified = rcgen::generate_simple_self_signed(names)?;
+    Ok(SelfSignedCert {
+        cert_pem: certified.cert.pem(),
+        key_pem: certified.key_pair.serialize_pem(),
+    })
+}
+
+fn write_file(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)
+}
+
+/// Load the certificate and key of `tls` for serving.
+pub async fn rustls_config(
+    tls: &TlsConfig,
+) -> std::io::Result<axum_server::tls_rustls::RustlsConfig> {
+    install_crypto_provider();
+    axum_server::tls_rustls::RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_generated_cert_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let tls = TlsConfig {
+            cert_path: dir.path().join("tls").join("cert.pem"),
+            key_path: dir.path().join("tls").join("key.pem"),
+        };
+        let cert = generate_self_signed(&["kicodex.lan".to_string()]).unwrap();
+        assert!(cert.cert_pem.starts_with("-----BEGIN CERTIFICATE-----"));
+        cert.write(&tls).unwrap();
+
+        rustls_config(&tls).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_missing_cert_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let tls = TlsConfig {
+            cert_path: dir.path().join("cert.pem"),
+            key_path: dir.path().join("key.pem"),
+        };
+        assert!(rustls_config(&tls).await.is_err());
+    }
+}
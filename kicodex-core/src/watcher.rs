@@ -1,83 +1,120 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
+use notify::Watcher;
 use notify_debouncer_mini::{new_debouncer, DebouncedEventKind};
 
-use crate::registry::{PersistedRegistry, ProjectRegistry};
+use crate::registry::{PersistedRegistry, ProjectEntry, ProjectRegistry};
 
 /// Start watching all registered library paths for changes.
-/// When a CSV or YAML file changes, the corresponding library is reloaded
-/// in the runtime registry.
+/// When a CSV or YAML file changes, the part tables that depend on it are
+/// reloaded in the runtime registry.
 ///
 /// This spawns a background tokio task and returns immediately.
 pub fn start_watching(
     persisted: &PersistedRegistry,
     registry: Arc<ProjectRegistry>,
 ) -> Result<(), notify::Error> {
-    // Build a map of watched directory -> (token, library_path) for reload lookup
-    let mut watch_entries: Vec<(PathBuf, String, PathBuf)> = Vec::new();
-    for entry in &persisted.projects {
-        let library_path = PathBuf::from(&entry.library_path);
-        if library_path.exists() {
-            watch_entries.push((library_path.clone(), entry.token.clone(), library_path));
-        }
-    }
-
-    if watch_entries.is_empty() {
+    if persisted.projects.is_empty() {
         return Ok(());
     }
+    spawn_watcher(persisted, registry, None)
+}
+
+/// Like `start_watching`, but also watch the registry file itself. When it
+/// changes, projects added elsewhere (`kicodex init`, the tray app) are
+/// loaded, removed ones are dropped, and library watches follow along.
+pub fn start_watching_with_registry_file(
+    registry_path: &Path,
+    persisted: &PersistedRegistry,
+    registry: Arc<ProjectRegistry>,
+) -> Result<(), notify::Error> {
+    spawn_watcher(persisted, registry, Some(registry_path.to_path_buf()))
+}
 
+fn spawn_watcher(
+    persisted: &PersistedRegistry,
+    registry: Arc<ProjectRegistry>,
+    registry_path: Option<PathBuf>,
+) -> Result<(), notify::Error> {
     let (tx, rx) = std::sync::mpsc::channel();
 
     let mut debouncer = new_debouncer(Duration::from_secs(1), tx)?;
 
-    for (watch_path, _, _) in &watch_entries {
+    let mut watched: HashSet<PathBuf> = HashSet::new();
+    let mut mtimes = FileTimes::default();
+    update_library_watches(debouncer.watcher(), &mut watched, &mut mtimes, &persisted.projects);
+
+    // Watch the registry file's directory: saving may replace the file
+    if let Some(registry_dir) = registry_path.as_deref().and_then(Path::parent) {
+        std::fs::create_dir_all(registry_dir).map_err(notify::Error::io)?;
         debouncer
             .watcher()
-            .watch(watch_path, notify::RecursiveMode::Recursive)?;
-        tracing::info!("Watching {} for changes", watch_path.display());
+            .watch(registry_dir, notify::RecursiveMode::NonRecursive)?;
+        tracing::info!("Watching {} for registry changes", registry_dir.display());
     }
 
     let registry_clone = registry;
-    let entries = watch_entries;
+    let mut entries = persisted.projects.clone();
 
     // Spawn a background thread to process file system events
     std::thread::spawn(move || {
-        // Keep the debouncer alive for the lifetime of this thread
-        let _debouncer = debouncer;
+        let mut debouncer = debouncer;
 
         loop {
             match rx.recv() {
                 Ok(Ok(events)) => {
+                    let mut changed = Vec::new();
                     for event in &events {
                         if event.kind != DebouncedEventKind::Any {
                             continue;
                         }
                         let path = &event.path;
+
+                        if registry_path.as_deref() == Some(path.as_path()) {
+                            match PersistedRegistry::load(path) {
+                                Ok(persisted) => {
+                                    apply_registry_changes(&registry_clone, &entries, &persisted.projects);
+                                    update_library_watches(
+                                        debouncer.watcher(),
+                                        &mut watched,
+                                        &mut mtimes,
+                                        &persisted.projects,
+                                    );
+                                    entries = persisted.projects;
+                                }
+                                Err(e) => {
+                                    // Possibly caught mid-write; the next event retries
+                                    tracing::warn!("Failed to reload registry file: {}", e);
+                                }
+                            }
+                            continue;
+                        }
+
                         let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
 
                         if !matches!(ext, "csv" | "yaml" | "yml") {
                             continue;
                         }
+                        // Reading a file (as every reload does) is reported too
+                        if !mtimes.changed(path) {
+                            continue;
+                        }
+                        changed.push(path.clone());
+                    }
 
-                        // Find which library this file belongs to
-                        for (watch_path, token, library_path) in &entries {
-                            if path.starts_with(watch_path) {
-                                tracing::info!(
-                                    "Change detected in {}, reloading library...",
-                                    path.display()
-                                );
-                                match registry_clone.reload(token, library_path) {
-                                    Ok(()) => {
-                                        tracing::info!("Library reloaded successfully");
-                                    }
-                                    Err(e) => {
-                                        tracing::error!("Failed to reload library: {}", e);
-                                    }
-                                }
-                                break;
-                            }
+                    // Reload every token serving a changed library (projects may share one)
+                    for entry in &entries {
+                        let library_path = Path::new(&entry.library_path);
+                        let library_changes: Vec<PathBuf> = changed
+                            .iter()
+                            .filter(|path| path.starts_with(library_path))
+                            .cloned()
+                            .collect();
+                        if !library_changes.is_empty() {
+                            reload_changed(&registry_clone, &entry.token, library_path, &library_changes);
                         }
                     }
                 }
@@ -94,3 +131,137 @@ pub fn start_watching(
 
     Ok(())
 }
+
+/// Reload the part tables of a token's library that depend on `changed`.
+fn reload_changed(registry: &ProjectRegistry, token: &str, library_path: &Path, changed: &[PathBuf]) {
+    for path in changed {
+        tracing::info!("Change detected in {}", path.display());
+    }
+    let started = Instant::now();
+    match registry.reload_changed(token, library_path, changed) {
+        Ok(Some(stats)) => {
+            tracing::info!(
+                "Reloaded {}: {} part table(s) rebuilt ({} parts), {} reused, in {:?}",
+                library_path.display(),
+                stats.part_tables_reloaded,
+                stats.parts_reloaded,
+                stats.part_tables_reused,
+                started.elapsed()
+            );
+        }
+        Ok(None) => {
+            tracing::debug!("No part table of {} depends on the changed files", library_path.display());
+        }
+        Err(e) => {
+            tracing::error!("Failed to reload library: {}", e);
+        }
+    }
+}
+
+/// Load tokens that are new or whose entries changed, and drop removed ones.
+fn apply_registry_changes(registry: &ProjectRegistry, old: &[ProjectEntry], new: &[ProjectEntry]) {
+    let changes = PersistedRegistry::diff(old, new);
+    for token in &changes.removed_tokens {
+        tracing::info!("Project removed from registry (token: {}...)", &token[..token.len().min(8)]);
+        registry.remove(token);
+    }
+    if !changes.changed_entries.is_empty() {
+        registry.load_entries(&changes.changed_entries);
+    }
+}
+
+/// Last seen modification times of watched library files, used to tell
+/// real changes from events fired by merely opening a file.
+#[derive(Default)]
+struct FileTimes(HashMap<PathBuf, Option<SystemTime>>);
+
+impl FileTimes {
+    /// Record the current modification time of every file under `dir`.
+    fn record_dir(&mut self, dir: &Path) {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                self.record_dir(&path);
+            } else {
+                self.0.insert(path.clone(), modified(&path));
+            }
+        }
+    }
+
+    /// Whether `path` was modified, created or removed since last seen.
+    fn changed(&mut self, path: &Path) -> bool {
+        let current = modified(path);
+        self.0.insert(path.to_path_buf(), current) != Some(current)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Watch the library paths of `entries` that exist, and stop watching paths
+/// no entry uses anymore.
+fn update_library_watches(
+    watcher: &mut dyn Watcher,
+    watched: &mut HashSet<PathBuf>,
+    mtimes: &mut FileTimes,
+    entries: &[ProjectEntry],
+) {
+    let wanted: HashSet<PathBuf> = entries
+        .iter()
+        .map(|e| PathBuf::from(&e.library_path))
+        .filter(|p| p.exists())
+        .collect();
+
+    let stale: Vec<PathBuf> = watched.difference(&wanted).cloned().collect();
+    for path in stale {
+        if let Err(e) = watcher.unwatch(&path) {
+            tracing::warn!("Failed to stop watching {}: {}", path.display(), e);
+        } else {
+            tracing::info!("Stopped watching {}", path.display());
+        }
+        watched.remove(&path);
+    }
+    for path in wanted {
+        if watched.contains(&path) {
+            continue;
+        }
+        match watcher.watch(&path, notify::RecursiveMode::Recursive) {
+            Ok(()) => {
+                tracing::info!("Watching {} for changes", path.display());
+                mtimes.record_dir(&path);
+                watched.insert(path);
+            }
+            Err(e) => tracing::warn!("Failed to watch {}: {}", path.display(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_times_ignore_reads() {
+        let tmp = tempfile::tempdir().unwrap();
+        let csv = tmp.path().join("data").join("parts.csv");
+        std::fs::create_dir_all(csv.parent().unwrap()).unwrap();
+        std::fs::write(&csv, "id\n1\n").unwrap();
+
+        let mut mtimes = FileTimes::default();
+        mtimes.record_dir(tmp.path());
+        std::fs::read_to_string(&csv).unwrap();
+        assert!(!mtimes.changed(&csv));
+
+        let file = std::fs::File::options().write(true).open(&csv).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        assert!(mtimes.changed(&csv));
+        assert!(!mtimes.changed(&csv));
+
+        std::fs::remove_file(&csv).unwrap();
+        assert!(mtimes.changed(&csv));
+    }
+}
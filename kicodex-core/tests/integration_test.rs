@@ -5,6 +5,23 @@ fn fixture_path() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample-library")
 }
 
+/// Helper: copy the fixture library into `dest` so a test can modify it.
+fn copy_fixture(dest: &std::path::Path) {
+    fn copy_dir(src: &std::path::Path, dest: &std::path::Path) {
+        std::fs::create_dir_all(dest).unwrap();
+        for entry in std::fs::read_dir(src).unwrap() {
+            let entry = entry.unwrap();
+            let target = dest.join(entry.file_name());
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir(&entry.path(), &target);
+            } else {
+                std::fs::copy(entry.path(), target).unwrap();
+            }
+        }
+    }
+    copy_dir(&fixture_path(), dest);
+}
+
 /// Helper: load a library into a single-project registry and build the router.
 fn build_test_app() -> axum::Router {
     let library = kicodex_core::server::load_library(&fixture_path()).unwrap();
@@ -66,6 +83,9 @@ async fn test_categories_endpoint() {
     assert_eq!(resp.len(), 1);
     assert_eq!(resp[0]["id"], "1");
     assert_eq!(resp[0]["name"], "Resistors");
+    // No description in library.yaml, so the template's description is used
+    assert_eq!(resp[0]["description"], "Fixed resistors");
+    assert!(resp[0].get("notes").is_none());
 }
 
 #[tokio::test]
@@ -198,6 +218,72 @@ async fn test_nonexistent_part_returns_404() {
     assert_eq!(resp.status(), 404);
 }
 
+#[tokio::test]
+async fn test_category_ids_survive_reordering() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    // Insert a new part table (without an id) above the existing one
+    std::fs::write(
+        tmp.path().join("library.yaml"),
+        r#"name: "Sample Components Library"
+templates_path: schemas
+part_tables:
+  - file: data/resistors.csv
+    template: resistor
+    name: "New Resistors"
+  - id: 1
+    file: data/resistors.csv
+    template: resistor
+    name: "Resistors"
+    description: "Legacy resistors"
+"#,
+    )
+    .unwrap();
+
+    let library = kicodex_core::server::load_library(tmp.path()).unwrap();
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("test-token", library);
+    let app = kicodex_core::server::build_router(Arc::new(registry));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+
+    let resp: Vec<serde_json::Value> = client
+        .get(format!("{url}/v1/categories.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.len(), 2);
+    assert_eq!(resp[0]["name"], "New Resistors");
+    assert_eq!(resp[0]["id"], "2");
+    assert_eq!(resp[1]["name"], "Resistors");
+    assert_eq!(resp[1]["id"], "1");
+    assert_eq!(resp[1]["description"], "Legacy resistors");
+
+    // The assigned id is written back to library.yaml
+    let manifest = kicodex_core::data::library::load_library_manifest(tmp.path()).unwrap();
+    assert_eq!(manifest.part_tables[0].id.as_deref(), Some("2"));
+
+    let resp = client
+        .get(format!("{url}/v1/parts/category/2.json"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
 // --- Phase 2: Multi-project auth tests ---
 
 #[tokio::test]
@@ -281,3 +367,1393 @@ async fn test_single_project_skips_auth() {
         .unwrap();
     assert_eq!(resp.status(), 200);
 }
+
+#[tokio::test]
+async fn test_name_and_description_formats() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    let template_path = tmp.path().join("schemas").join("resistor.yaml");
+    let template = std::fs::read_to_string(&template_path).unwrap();
+    std::fs::write(
+        &template_path,
+        format!(
+            "name_format: \"{{value}} {{tolerance}} {{package}}\"\n\
+             description_format: \"{{manufacturer}} {{power_rating}}\"\n{template}"
+        ),
+    )
+    .unwrap();
+
+    let library = kicodex_core::server::load_library(tmp.path()).unwrap();
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("test-token", library);
+    let app = kicodex_core::server::build_router(Arc::new(registry));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+
+    let parts: Vec<serde_json::Value> = client
+        .get(format!("{url}/v1/parts/category/1.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(parts[0]["name"], "10K 1% 0603");
+    assert_eq!(parts[0]["description"], "Yageo 1/10W");
+
+    let detail: serde_json::Value = client
+        .get(format!("{url}/v1/parts/1.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(detail["name"], "10K 1% 0603");
+    // The description field itself is still the raw column
+    assert_eq!(
+        detail["fields"]["Description"]["value"],
+        "RES 10K OHM 1% 1/10W 0603"
+    );
+}
+
+#[tokio::test]
+async fn test_computed_fields() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    let template_path = tmp.path().join("schemas").join("resistor.yaml");
+    let mut template = std::fs::read_to_string(&template_path).unwrap();
+    template.push_str(concat!(
+        "  sim_params:\n",
+        "    display_name: \"Sim.Params\"\n",
+        "    expr: \"r={sim_value}\"\n",
+        "  sim_value:\n",
+        "    display_name: \"Sim Value\"\n",
+        "    expr: \"{resistance}\"\n",
+    ));
+    std::fs::write(&template_path, template).unwrap();
+    let csv_path = tmp.path().join("data").join("resistors.csv");
+    let csv_before = std::fs::read_to_string(&csv_path).unwrap();
+
+    let library = kicodex_core::server::load_library(tmp.path()).unwrap();
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("test-token", library);
+    let app = kicodex_core::server::build_router(Arc::new(registry));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let detail: serde_json::Value = reqwest::Client::new()
+        .get(format!("{url}/v1/parts/1.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(detail["fields"]["Sim Value"]["value"], "10K");
+    assert_eq!(detail["fields"]["Sim.Params"]["value"], "r=10K");
+
+    // Computed values are never written back to the CSV
+    assert_eq!(std::fs::read_to_string(&csv_path).unwrap(), csv_before);
+}
+
+#[tokio::test]
+async fn test_internal_columns_not_served() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    // Mark power_rating and mpn internal in the template, refer to internal
+    // columns in its formats and add an underscore column
+    let template_path = tmp.path().join("schemas").join("resistor.yaml");
+    let template = std::fs::read_to_string(&template_path).unwrap();
+    std::fs::write(
+        &template_path,
+        template
+            .replace(
+                "    display_name: \"Power Rating\"\n",
+                "    display_name: \"Power Rating\"\n    export: false\n",
+            )
+            .replace(
+                "fields:\n",
+                "name_format: \"{power_rating} {_cost}\"\ndescription_format: \"{_cost}\"\nfields:\n  mpn:\n    display_name: \"MPN\"\n    export: false\n",
+            ),
+    )
+    .unwrap();
+    let csv_path = tmp.path().join("data").join("resistors.csv");
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    let csv: String = csv
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let extra = if i == 0 { "_cost" } else { "0.002" };
+            format!("{line},{extra}\n")
+        })
+        .collect();
+    std::fs::write(&csv_path, csv).unwrap();
+
+    let library = kicodex_core::server::load_library(tmp.path()).unwrap();
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("test-token", library);
+    let app = kicodex_core::server::build_router(Arc::new(registry));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let detail: serde_json::Value = reqwest::Client::new()
+        .get(format!("{url}/v1/parts/1.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let fields = detail["fields"].as_object().unwrap();
+    assert!(fields.contains_key("Tolerance"));
+    assert!(!fields.contains_key("Power Rating"));
+    assert!(!fields.contains_key("_cost"));
+    // Internal columns render as empty, so the name falls back to the value
+    assert_eq!(detail["name"], "10K");
+
+    let parts: serde_json::Value = reqwest::Client::new()
+        .get(format!("{url}/v1/parts/category/1.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(parts[0]["name"], "10K");
+    assert_eq!(parts[0]["description"], "RES 10K OHM 1% 1/10W 0603");
+}
+
+#[tokio::test]
+async fn test_non_active_parts_hidden_but_resolvable() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    let csv_path = tmp.path().join("data").join("resistors.csv");
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    let extra = ["lifecycle,replaced_by", ",", "obsolete,1", "draft,"];
+    let csv: String = csv
+        .lines()
+        .zip(extra)
+        .map(|(line, extra)| format!("{line},{extra}\n"))
+        .collect();
+    std::fs::write(&csv_path, csv).unwrap();
+
+    let library = kicodex_core::server::load_library(tmp.path()).unwrap();
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("test-token", library);
+    let app = kicodex_core::server::build_router(Arc::new(registry));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+
+    let parts: Vec<serde_json::Value> = client
+        .get(format!("{url}/v1/parts/category/1.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let ids: Vec<&str> = parts.iter().map(|p| p["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, vec!["1"]);
+
+    // Obsolete parts still resolve for existing schematics
+    let detail: serde_json::Value = client
+        .get(format!("{url}/v1/parts/2.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(detail["id"], "2");
+    assert_eq!(detail["fields"]["lifecycle"]["value"], "obsolete");
+    assert_eq!(detail["fields"]["replaced_by"]["value"], "1");
+}
+
+#[tokio::test]
+async fn test_view_categories() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    let manifest_path = tmp.path().join("library.yaml");
+    let mut manifest = std::fs::read_to_string(&manifest_path).unwrap();
+    manifest.push_str(concat!(
+        "views:\n",
+        "  - id: 100\n",
+        "    name: \"Big resistors\"\n",
+        "    description: \"100K and up\"\n",
+        "    part_tables: [Resistors]\n",
+        "    filter: \"value == 100K or value contains M\"\n",
+    ));
+    std::fs::write(&manifest_path, manifest).unwrap();
+
+    let library = kicodex_core::server::load_library(tmp.path()).unwrap();
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("test-token", library);
+    let app = kicodex_core::server::build_router(Arc::new(registry));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+
+    let categories: Vec<serde_json::Value> = client
+        .get(format!("{url}/v1/categories.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(categories.len(), 2);
+    assert_eq!(categories[1]["id"], "100");
+    assert_eq!(categories[1]["name"], "Big resistors");
+    assert_eq!(categories[1]["description"], "100K and up");
+
+    let parts: Vec<serde_json::Value> = client
+        .get(format!("{url}/v1/parts/category/100.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let ids: Vec<&str> = parts.iter().map(|p| p["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, vec!["2"]);
+
+    // Parts in a view resolve through the normal detail route
+    let resp = client
+        .get(format!("{url}/v1/parts/{}.json", ids[0]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[test]
+fn test_view_with_bad_filter_fails_to_load() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    let manifest_path = tmp.path().join("library.yaml");
+    let mut manifest = std::fs::read_to_string(&manifest_path).unwrap();
+    manifest.push_str("views:\n  - name: Broken\n    filter: \"value = 10K\"\n");
+    std::fs::write(&manifest_path, manifest).unwrap();
+
+    let err = kicodex_core::server::load_library(tmp.path()).unwrap_err();
+    assert!(err.to_string().contains("view 'Broken'"), "{err}");
+}
+
+/// Helper: write a library with `rows_per_table` generated rows in each of two part tables.
+fn write_large_library(dir: &std::path::Path, rows_per_table: usize) {
+    copy_fixture(dir);
+    std::fs::write(
+        dir.join("library.yaml"),
+        r#"name: "Large Library"
+templates_path: schemas
+part_tables:
+  - id: 1
+    file: data/resistors.csv
+    template: resistor
+    name: "Resistors"
+  - id: 2
+    file: data/capacitors.csv
+    template: _base
+    name: "Capacitors"
+"#,
+    )
+    .unwrap();
+
+    for (file, prefix) in [("resistors.csv", "R"), ("capacitors.csv", "C")] {
+        let mut csv = String::from("id,mpn,value,symbol,footprint\n");
+        for i in 0..rows_per_table {
+            csv.push_str(&format!(
+                "{prefix}{i},{prefix}-MPN-{i},{i},Device:{prefix},Lib:{prefix}_0402\n"
+            ));
+        }
+        std::fs::write(dir.join("data").join(file), csv).unwrap();
+    }
+}
+
+/// Helper: load a generated library and resolve every part through the index.
+fn assert_indexed_lookups(rows_per_table: usize) {
+    let tmp = tempfile::TempDir::new().unwrap();
+    write_large_library(tmp.path(), rows_per_table);
+
+    let library = kicodex_core::server::load_library(tmp.path()).unwrap();
+    assert_eq!(library.part_index.len(), rows_per_table * 2);
+
+    // Look up every part once; a linear scan would be quadratic here
+    let ids: Vec<String> = library
+        .part_tables
+        .iter()
+        .flat_map(|ct| ct.components.iter().map(|r| r["id"].clone()))
+        .collect();
+    let start = std::time::Instant::now();
+    for id in &ids {
+        let (ct, row) = library.find_part(id).unwrap();
+        assert_eq!(&row["id"], id);
+        assert_eq!(ct.id, if id.starts_with('R') { "1" } else { "2" });
+    }
+    let lookup_time = start.elapsed();
+    assert!(
+        lookup_time < std::time::Duration::from_secs(2),
+        "indexed lookups took {:?}",
+        lookup_time
+    );
+    assert!(library.find_part("missing").is_none());
+}
+
+#[test]
+fn test_part_lookup_uses_index() {
+    assert_indexed_lookups(500);
+}
+
+#[test]
+#[ignore = "generates a 40k-row library; run with --ignored"]
+fn bench_part_lookup_large_library() {
+    assert_indexed_lookups(20_000);
+}
+
+#[test]
+fn test_part_index_rebuilt_on_reload() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    write_large_library(tmp.path(), 10);
+
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("t", kicodex_core::server::load_library(tmp.path()).unwrap());
+    let before = registry.get("t").unwrap();
+    assert!(before.find_part("R-new").is_none());
+
+    kicodex_core::data::csv_loader::append_row(
+        &tmp.path().join("data").join("resistors.csv"),
+        &indexmap::IndexMap::from([("mpn".to_string(), "NEW".to_string())]),
+    )
+    .unwrap();
+    registry.reload("t", tmp.path()).unwrap();
+
+    let after = registry.get("t").unwrap();
+    assert_eq!(after.part_index.len(), 21);
+    // The previously handed-out snapshot keeps its own consistent index
+    assert_eq!(before.part_index.len(), 20);
+    let new_id = after.part_tables[0].components.last().unwrap()["id"].clone();
+    let (_, row) = after.find_part(&new_id).unwrap();
+    assert_eq!(row["mpn"], "NEW");
+}
+
+#[test]
+fn test_reload_scope_maps_files_to_part_tables() {
+    use kicodex_core::server::{reload_scope, ReloadScope};
+
+    let tmp = tempfile::TempDir::new().unwrap();
+    write_large_library(tmp.path(), 1);
+    let root = tmp.path();
+    let scope = |rel: &str| reload_scope(root, &[root.join(rel)]);
+    let tables = |names: &[&str]| ReloadScope::PartTables(names.iter().map(|n| n.to_string()).collect());
+
+    assert_eq!(scope("data/resistors.csv"), tables(&["Resistors"]));
+    assert_eq!(scope("schemas/resistor.yaml"), tables(&["Resistors"]));
+    // Every template inherits from the base template
+    assert_eq!(scope("schemas/_base.yaml"), tables(&["Resistors", "Capacitors"]));
+    assert_eq!(scope("library.yaml"), ReloadScope::Full);
+    assert_eq!(scope("schemas/unused.yaml"), ReloadScope::Unaffected);
+    assert_eq!(scope("data/.resistors.csv.tmp"), ReloadScope::Unaffected);
+    assert_eq!(scope(".git/config.yaml"), ReloadScope::Unaffected);
+    assert_eq!(
+        reload_scope(root, &[root.join("data/resistors.csv"), root.join("data/capacitors.csv")]),
+        tables(&["Resistors", "Capacitors"])
+    );
+}
+
+#[test]
+fn test_reload_changed_only_reloads_affected_tables() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    write_large_library(tmp.path(), 10);
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("t", kicodex_core::server::load_library(tmp.path()).unwrap());
+
+    for file in ["resistors.csv", "capacitors.csv"] {
+        kicodex_core::data::csv_loader::append_row(
+            &tmp.path().join("data").join(file),
+            &indexmap::IndexMap::from([("mpn".to_string(), "NEW".to_string())]),
+        )
+        .unwrap();
+    }
+    // Only the resistor CSV is reported changed, so capacitors are reused as loaded
+    let stats = registry
+        .reload_changed("t", tmp.path(), &[tmp.path().join("data").join("resistors.csv")])
+        .unwrap()
+        .unwrap();
+    assert_eq!(stats.part_tables_reloaded, 1);
+    assert_eq!(stats.part_tables_reused, 1);
+    assert_eq!(stats.parts_reloaded, 11);
+
+    let library = registry.get("t").unwrap();
+    assert_eq!(library.part_tables[0].components.len(), 11);
+    assert_eq!(library.part_tables[1].components.len(), 10);
+    assert_eq!(library.part_index.len(), 21);
+
+    let unrelated = tmp.path().join("schemas").join("notes.yaml");
+    std::fs::write(&unrelated, "fields: {}\n").unwrap();
+    assert!(registry.reload_changed("t", tmp.path(), &[unrelated]).unwrap().is_none());
+}
+
+#[test]
+fn test_read_only_mode_never_writes_library_files() {
+    use kicodex_core::server::LoadMode;
+
+    let tmp = tempfile::TempDir::new().unwrap();
+    write_large_library(tmp.path(), 3);
+    let manifest_path = tmp.path().join("library.yaml");
+    let manifest = std::fs::read_to_string(&manifest_path)
+        .unwrap()
+        .replace("  - id: 2\n    file", "  - file");
+    std::fs::write(&manifest_path, &manifest).unwrap();
+    let csv_path = tmp.path().join("data").join("resistors.csv");
+    let csv = "mpn,value,symbol,footprint\nR-1,1k,Device:R,Lib:R_0402\nR-2,2k,Device:R,Lib:R_0402\n";
+    std::fs::write(&csv_path, csv).unwrap();
+
+    let registry = kicodex_core::registry::ProjectRegistry::new().with_load_mode(LoadMode::ReadOnly);
+    registry.insert(
+        "t",
+        kicodex_core::server::load_library_with_mode(tmp.path(), &Default::default(), LoadMode::ReadOnly)
+            .unwrap(),
+    );
+    registry.reload("t", tmp.path()).unwrap();
+    assert_eq!(std::fs::read_to_string(&manifest_path).unwrap(), manifest);
+    assert_eq!(std::fs::read_to_string(&csv_path).unwrap(), csv);
+
+    // IDs are assigned in memory, the same way on every load
+    let library = registry.get("t").unwrap();
+    assert_eq!(library.part_tables[1].id, "2");
+    let id = library.part_tables[0].components[0]["id"].clone();
+    assert!(library.find_part(&id).is_some());
+    let again =
+        kicodex_core::server::load_library_with_mode(tmp.path(), &Default::default(), LoadMode::ReadOnly)
+            .unwrap();
+    assert_eq!(again.part_tables[0].components[0]["id"], id);
+
+    // The default mode writes them back
+    kicodex_core::server::load_library(tmp.path()).unwrap();
+    assert!(std::fs::read_to_string(&csv_path).unwrap().starts_with("id,"));
+}
+
+#[tokio::test]
+async fn test_aggregated_libraries() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let passives = tmp.path().join("passives");
+    let spares = tmp.path().join("spares");
+    copy_fixture(&passives);
+    copy_fixture(&spares);
+
+    let member = |name: &str, path: &std::path::Path| kicodex_core::registry::AggregateMember {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        filter: Default::default(),
+        library: Arc::new(kicodex_core::server::load_library(path).unwrap()),
+    };
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::new());
+    registry.insert_aggregate(
+        "group-token",
+        "project-parts",
+        vec![member("passives", &passives), member("spares", &spares)],
+    );
+    let app = kicodex_core::server::build_router(registry.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+
+    let categories: Vec<serde_json::Value> = client
+        .get(format!("{url}/v1/categories.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let ids: Vec<&str> = categories.iter().map(|c| c["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, vec!["passives:1", "spares:1"]);
+    assert_eq!(categories[1]["name"], "spares / Resistors");
+
+    // Both members have a part "1"; namespacing keeps them apart
+    let parts: Vec<serde_json::Value> = client
+        .get(format!("{url}/v1/parts/category/spares:1.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(parts[0]["id"], "spares:1");
+    let resp = client
+        .get(format!("{url}/v1/parts/passives:1.json"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Reloading one member rebuilds the merged library
+    kicodex_core::data::csv_loader::append_row(
+        &spares.join("data").join("resistors.csv"),
+        &indexmap::IndexMap::from([("mpn".to_string(), "NEW".to_string())]),
+    )
+    .unwrap();
+    registry.reload("group-token", &spares).unwrap();
+    let merged = registry.get("group-token").unwrap();
+    assert_eq!(merged.part_tables[0].components.len(), 3);
+    assert_eq!(merged.part_tables[1].components.len(), 4);
+}
+
+#[test]
+fn test_project_part_filters() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let lib_dir = tmp.path().join("shared-lib");
+    copy_fixture(&lib_dir);
+
+    // Two projects share one library; one may only use the 10K resistor
+    let hobby = tmp.path().join("hobby");
+    let medical = tmp.path().join("medical");
+    std::fs::create_dir_all(&hobby).unwrap();
+    std::fs::create_dir_all(&medical).unwrap();
+    let lib_path = lib_dir.to_string_lossy().to_string();
+    std::fs::write(
+        hobby.join("kicodex.yaml"),
+        format!("libraries:\n  - name: parts\n    path: {lib_path}\n"),
+    )
+    .unwrap();
+    std::fs::write(
+        medical.join("kicodex.yaml"),
+        format!("libraries:\n  - name: parts\n    path: {lib_path}\n    where: \"value == 10K\"\n"),
+    )
+    .unwrap();
+
+    let entry = |token: &str, project: &std::path::Path| kicodex_core::registry::ProjectEntry {
+        token: token.to_string(),
+        project_path: Some(project.to_string_lossy().to_string()),
+        library_path: lib_path.clone(),
+        name: "parts".to_string(),
+        description: None,
+        group: None,
+        access: Default::default(),
+    };
+    let persisted = kicodex_core::registry::PersistedRegistry {
+        projects: vec![entry("hobby", &hobby), entry("medical", &medical)],
+    };
+    let registry = kicodex_core::registry::ProjectRegistry::from_persisted(&persisted).unwrap();
+
+    let hobby_lib = registry.get("hobby").unwrap();
+    assert_eq!(hobby_lib.part_tables[0].components.len(), 3);
+    let medical_lib = registry.get("medical").unwrap();
+    assert_eq!(medical_lib.part_tables[0].components.len(), 1);
+    assert!(medical_lib.find_part("1").is_some());
+    assert!(medical_lib.find_part("2").is_none());
+
+    // The filter is reapplied on reload
+    registry.reload("medical", &lib_dir).unwrap();
+    assert_eq!(registry.get("medical").unwrap().part_tables[0].components.len(), 1);
+
+    // Excluding the only table leaves no categories
+    std::fs::write(
+        medical.join("kicodex.yaml"),
+        format!("libraries:\n  - name: parts\n    path: {lib_path}\n    exclude: [Resistors]\n"),
+    )
+    .unwrap();
+    let registry = kicodex_core::registry::ProjectRegistry::from_persisted(&persisted).unwrap();
+    assert!(registry.get("medical").unwrap().part_tables.is_empty());
+}
+
+#[tokio::test]
+async fn test_conditional_requests() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::new());
+    registry.insert("test-token", kicodex_core::server::load_library(tmp.path()).unwrap());
+    let app = kicodex_core::server::build_router(registry.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{url}/v1/categories.json"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let etag = resp.headers()["etag"].to_str().unwrap().to_string();
+    let last_modified = resp.headers()["last-modified"].to_str().unwrap().to_string();
+
+    // Matching validators get 304 with no body
+    let resp = client
+        .get(format!("{url}/v1/categories.json"))
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 304);
+    assert_eq!(resp.headers()["etag"], etag.as_str());
+    assert!(resp.bytes().await.unwrap().is_empty());
+
+    let resp = client
+        .get(format!("{url}/v1/parts/category/1.json"))
+        .header("If-Modified-Since", &last_modified)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 304);
+
+    // If-None-Match takes precedence over If-Modified-Since
+    let resp = client
+        .get(format!("{url}/v1/categories.json"))
+        .header("If-None-Match", "\"stale\"")
+        .header("If-Modified-Since", &last_modified)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Reloading unchanged files keeps the ETag
+    registry.reload("test-token", tmp.path()).unwrap();
+    let resp = client
+        .get(format!("{url}/v1/categories.json"))
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 304);
+
+    // Changing the data and reloading changes the ETag
+    let csv_path = tmp.path().join("data").join("resistors.csv");
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    std::fs::write(&csv_path, csv.replace("RES 1K OHM", "RES 1.0K OHM")).unwrap();
+    registry.reload("test-token", tmp.path()).unwrap();
+
+    let resp = client
+        .get(format!("{url}/v1/categories.json"))
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_ne!(resp.headers()["etag"], etag.as_str());
+}
+
+/// Read from an SSE response until `needle` shows up, returning everything read.
+async fn read_events_until(resp: &mut reqwest::Response, needle: &str) -> String {
+    let mut received = String::new();
+    while !received.contains(needle) {
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), resp.chunk())
+            .await
+            .expect("timed out waiting for event")
+            .unwrap()
+            .expect("event stream closed");
+        received.push_str(&String::from_utf8_lossy(&chunk));
+    }
+    received
+}
+
+#[tokio::test]
+async fn test_change_events() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::new());
+    registry.insert("token-a", kicodex_core::server::load_library(tmp.path()).unwrap());
+    registry.insert("token-b", kicodex_core::server::load_library(tmp.path()).unwrap());
+    let app = kicodex_core::server::build_router(registry.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+    let resp = client.get(format!("{url}/v1/events")).send().await.unwrap();
+    assert_eq!(resp.status(), 401);
+
+    let mut resp = client
+        .get(format!("{url}/v1/events"))
+        .header("Authorization", "Token token-a")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "text/event-stream");
+
+    // Events for other tokens are not delivered
+    registry.reload("token-b", tmp.path()).unwrap();
+
+    let csv_path = tmp.path().join("data").join("resistors.csv");
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    std::fs::write(&csv_path, csv.replace("RES 1K OHM", "RES 1.0K OHM")).unwrap();
+    registry.reload("token-a", tmp.path()).unwrap();
+    let received = read_events_until(&mut resp, "event: reloaded").await;
+    assert_eq!(received.matches("event: reloaded").count(), 1);
+    assert!(received.contains(r#""changed_tables":[{"id":"1","name":"Resistors"}]"#));
+
+    std::fs::write(tmp.path().join("library.yaml"), "not: [valid").unwrap();
+    assert!(registry.reload("token-a", tmp.path()).is_err());
+    let received = read_events_until(&mut resp, "event: reload_failed").await;
+    assert!(received.contains(r#""error":"#));
+
+    registry.remove("token-a");
+    read_events_until(&mut resp, "event: removed").await;
+}
+
+#[tokio::test]
+async fn test_admin_api_rejects_remote_peers() {
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::new());
+    let admin = || kicodex_core::admin::admin_router(registry.clone(), "admin-secret");
+    let remote = serve_app(admin().layer(axum::extract::connect_info::MockConnectInfo(
+        std::net::SocketAddr::from(([192, 168, 1, 20], 50000)),
+    )))
+    .await;
+    // Without connect info the peer is unknown, which is rejected too
+    let unknown = serve_app(admin()).await;
+    let local = serve_app(admin().layer(axum::extract::connect_info::MockConnectInfo(
+        std::net::SocketAddr::from(([127, 0, 0, 1], 50000)),
+    )))
+    .await;
+
+    let client = reqwest::Client::new();
+    for (url, status) in [(remote, 403), (unknown, 403), (local, 200)] {
+        let resp = client
+            .get(format!("{url}/internal/status"))
+            .header("Authorization", "Token admin-secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), status, "{url}");
+    }
+}
+
+#[tokio::test]
+async fn test_admin_api() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let lib_dir = tmp.path().join("lib");
+    copy_fixture(&lib_dir);
+
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::new());
+    let app = kicodex_core::server::build_router(registry.clone())
+        .merge(kicodex_core::admin::admin_router(registry.clone(), "admin-secret"));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let url = format!("http://{addr}");
+
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{url}/internal/status"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = client
+        .get(format!("{url}/internal/status"))
+        .header("Authorization", "Token wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+
+    let entry = |token: &str, library_path: &std::path::Path| {
+        serde_json::json!({
+            "token": token,
+            "library_path": library_path.to_string_lossy(),
+            "name": "passives",
+        })
+    };
+    let status: serde_json::Value = client
+        .post(format!("{url}/internal/register"))
+        .header("Authorization", "Token admin-secret")
+        .json(&serde_json::json!({
+            "entries": [entry("good", &lib_dir), entry("bad", &tmp.path().join("missing"))]
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let status = status.as_array().unwrap();
+    assert_eq!(status.len(), 2);
+    let bad = status.iter().find(|s| s["token"] == "bad").unwrap();
+    assert_eq!(bad["loaded"], false);
+    assert!(bad["error"].is_string());
+    let good = status.iter().find(|s| s["token"] == "good").unwrap();
+    assert_eq!(good["loaded"], true);
+    assert_eq!(good["parts"], 3);
+
+    // The newly registered library is served right away
+    let resp = client
+        .get(format!("{url}/v1/categories.json"))
+        .header("Authorization", "Token good")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let projects: serde_json::Value = client
+        .get(format!("{url}/internal/projects"))
+        .header("Authorization", "Token admin-secret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(projects.as_array().unwrap().len(), 2);
+
+    // Reload picks up changes on disk
+    kicodex_core::data::csv_loader::append_row(
+        &lib_dir.join("data").join("resistors.csv"),
+        &indexmap::IndexMap::from([("mpn".to_string(), "NEW".to_string())]),
+    )
+    .unwrap();
+    let status: serde_json::Value = client
+        .post(format!("{url}/internal/reload"))
+        .header("Authorization", "Token admin-secret")
+        .json(&serde_json::json!({ "token": "good" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(status[0]["parts"], 4);
+    let resp = client
+        .post(format!("{url}/internal/reload"))
+        .header("Authorization", "Token admin-secret")
+        .json(&serde_json::json!({ "token": "unknown" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = client
+        .post(format!("{url}/internal/unregister"))
+        .header("Authorization", "Token admin-secret")
+        .json(&serde_json::json!({ "tokens": ["good", "bad"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+    assert!(registry.get("good").is_none());
+    assert!(registry.status().is_empty());
+}
+
+/// Serve a router on an ephemeral port and return its base URL.
+async fn serve_app(app: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{addr}")
+}
+
+#[tokio::test]
+async fn test_strict_mode_requires_token_with_single_library() {
+    let library = kicodex_core::server::load_library(&fixture_path()).unwrap();
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("only-token", library);
+    let policy = kicodex_core::middleware::AccessPolicy {
+        strict: true,
+        allowed_hosts: Vec::new(),
+    };
+    let url = serve_app(kicodex_core::server::build_router_with_policy(Arc::new(registry), policy)).await;
+
+    let client = reqwest::Client::new();
+    let resp = client.get(format!("{url}/v1/")).send().await.unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = client
+        .get(format!("{url}/v1/"))
+        .header("Authorization", "Token wrong-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = client
+        .get(format!("{url}/v1/"))
+        .header("Authorization", "Token only-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_host_allowlist() {
+    let library = kicodex_core::server::load_library(&fixture_path()).unwrap();
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("only-token", library);
+    let policy = kicodex_core::middleware::AccessPolicy {
+        strict: false,
+        allowed_hosts: vec!["127.0.0.1".to_string(), "localhost".to_string()],
+    };
+    let url = serve_app(kicodex_core::server::build_router_with_policy(Arc::new(registry), policy)).await;
+
+    let client = reqwest::Client::new();
+    let resp = client.get(format!("{url}/v1/")).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .get(format!("{url}/v1/"))
+        .header("Host", "localhost:18734")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // A rebound DNS name pointing at the server is rejected
+    let resp = client
+        .get(format!("{url}/v1/"))
+        .header("Host", "attacker.example:18734")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+}
+
+#[tokio::test]
+async fn test_token_access_restrictions() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    let entry = |token: &str, access: kicodex_core::registry::TokenAccess| {
+        kicodex_core::registry::ProjectEntry {
+            token: token.to_string(),
+            project_path: None,
+            library_path: tmp.path().to_string_lossy().to_string(),
+            name: token.to_string(),
+            description: None,
+            group: None,
+            access,
+        }
+    };
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.load_entries(&[
+        entry("normal", Default::default()),
+        entry(
+            "reader",
+            kicodex_core::registry::TokenAccess {
+                read_only: true,
+                ..Default::default()
+            },
+        ),
+        entry(
+            "expired",
+            kicodex_core::registry::TokenAccess {
+                expires_at: Some(chrono::Utc::now() - chrono::Duration::hours(1)),
+                ..Default::default()
+            },
+        ),
+        entry(
+            "valid",
+            kicodex_core::registry::TokenAccess {
+                expires_at: Some(chrono::Utc::now() + chrono::Duration::hours(1)),
+                ..Default::default()
+            },
+        ),
+    ]);
+    let url = serve_app(kicodex_core::server::build_router(Arc::new(registry))).await;
+
+    let client = reqwest::Client::new();
+    let get = |token: &str| {
+        client
+            .get(format!("{url}/v1/categories.json"))
+            .header("Authorization", format!("Token {token}"))
+            .send()
+    };
+    assert_eq!(get("expired").await.unwrap().status(), 401);
+    assert_eq!(get("valid").await.unwrap().status(), 200);
+    assert_eq!(get("reader").await.unwrap().status(), 200);
+
+    let post = |token: &str| {
+        client
+            .post(format!("{url}/v1/categories.json"))
+            .header("Authorization", format!("Token {token}"))
+            .send()
+    };
+    // Read-only tokens are refused before routing; others reach the router
+    assert_eq!(post("reader").await.unwrap().status(), 403);
+    assert_eq!(post("normal").await.unwrap().status(), 405);
+}
+
+#[tokio::test]
+async fn test_rotated_token_grace_window() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let project_dir = tmp.path().join("project");
+    let lib_dir = project_dir.join("lib");
+    copy_fixture(&lib_dir);
+
+    let mut persisted = kicodex_core::registry::PersistedRegistry::default();
+    persisted.upsert(kicodex_core::registry::ProjectEntry {
+        token: "old-token".to_string(),
+        project_path: Some(project_dir.to_string_lossy().to_string()),
+        library_path: lib_dir.to_string_lossy().to_string(),
+        name: "passives".to_string(),
+        description: None,
+        group: None,
+        access: Default::default(),
+    });
+    // A second library so tokens are checked
+    persisted.upsert(kicodex_core::registry::ProjectEntry {
+        token: "other-token".to_string(),
+        project_path: None,
+        library_path: lib_dir.to_string_lossy().to_string(),
+        name: "standalone".to_string(),
+        description: None,
+        group: None,
+        access: Default::default(),
+    });
+    let new_token = kicodex_core::discovery::auto_register::rotate_token(
+        &mut persisted,
+        "old-token",
+        std::time::Duration::from_secs(3600),
+        "http://127.0.0.1:18734",
+    )
+    .unwrap();
+
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::new());
+    registry.load_entries(&persisted.projects);
+    let url = serve_app(kicodex_core::server::build_router(registry.clone())).await;
+
+    let client = reqwest::Client::new();
+    let status = |token: String| {
+        let request = client
+            .get(format!("{url}/v1/categories.json"))
+            .header("Authorization", format!("Token {token}"))
+            .send();
+        async move { request.await.unwrap().status() }
+    };
+    assert_eq!(status(new_token.clone()).await, 200);
+    // The old token still works during the grace window
+    assert_eq!(status("old-token".to_string()).await, 200);
+
+    // Rotating without grace revokes the older tokens
+    let newest = kicodex_core::discovery::auto_register::rotate_token(
+        &mut persisted,
+        &new_token,
+        std::time::Duration::ZERO,
+        "http://127.0.0.1:18734",
+    )
+    .unwrap();
+    registry.remove(&new_token);
+    registry.load_entries(&persisted.projects);
+    assert_eq!(status(newest).await, 200);
+    assert_eq!(status(new_token).await, 401);
+    assert_eq!(status("old-token".to_string()).await, 401);
+}
+
+#[tokio::test]
+async fn test_serve_over_tls() {
+    let tmp = tempfile::tempdir().unwrap();
+    let tls = kicodex_core::config::TlsConfig {
+        cert_path: tmp.path().join("cert.pem"),
+        key_path: tmp.path().join("key.pem"),
+    };
+    let cert = kicodex_core::tls::generate_self_signed(&[]).unwrap();
+    cert.write(&tls).unwrap();
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = build_test_app();
+    let server_tls = tls.clone();
+    tokio::spawn(async move {
+        kicodex_core::server::serve_tls(listener, app, &server_tls)
+            .await
+            .unwrap();
+    });
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(cert.cert_pem.as_bytes()).unwrap())
+        .build()
+        .unwrap();
+    let resp = client
+        .get(format!("https://{addr}/v1/"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Plain HTTP is not served on a TLS port
+    let plain = reqwest::get(format!("http://{addr}/v1/")).await;
+    assert!(!plain.is_ok_and(|r| r.status().is_success()));
+}
+
+#[tokio::test]
+async fn test_failed_library_serves_load_error_category() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+    std::fs::write(tmp.path().join("library.yaml"), "not: [valid").unwrap();
+
+    let persisted = kicodex_core::registry::PersistedRegistry {
+        projects: vec![kicodex_core::registry::ProjectEntry {
+            token: "broken".to_string(),
+            project_path: None,
+            library_path: tmp.path().to_string_lossy().to_string(),
+            name: "passives".to_string(),
+            description: None,
+            group: None,
+            access: Default::default(),
+        }],
+    };
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::from_persisted(&persisted).unwrap());
+    let status = registry.status();
+    assert!(!status[0].loaded);
+    assert!(status[0].error.is_some());
+
+    let url = serve_app(kicodex_core::server::build_router(registry.clone())).await;
+    let client = reqwest::Client::new();
+    let get = |path: &str| {
+        client
+            .get(format!("{url}/v1/{path}"))
+            .header("Authorization", "Token broken")
+            .send()
+    };
+
+    let categories: serde_json::Value = get("categories.json").await.unwrap().json().await.unwrap();
+    let categories = categories.as_array().unwrap();
+    assert_eq!(categories.len(), 1);
+    assert_eq!(categories[0]["id"], kicodex_core::server::LOAD_ERROR_CATEGORY_ID);
+    let name = categories[0]["name"].as_str().unwrap();
+    assert!(name.starts_with("Library failed to load: "), "{name}");
+
+    let parts: serde_json::Value = get("parts/category/load-error.json").await.unwrap().json().await.unwrap();
+    assert_eq!(parts, serde_json::json!([]));
+
+    // Once fixed, the real library replaces the placeholder
+    copy_fixture(tmp.path());
+    registry.reload("broken", tmp.path()).unwrap();
+    let categories: serde_json::Value = get("categories.json").await.unwrap().json().await.unwrap();
+    assert!(categories
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|c| c["id"] != kicodex_core::server::LOAD_ERROR_CATEGORY_ID));
+    assert!(registry.status()[0].loaded);
+}
+
+#[test]
+fn test_broken_part_table_is_skipped_with_diagnostic() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+    let manifest_path = tmp.path().join("library.yaml");
+    let mut manifest = std::fs::read_to_string(&manifest_path).unwrap();
+    manifest.push_str(
+        r#"  - id: 2
+    file: data/capacitors.csv
+    schema: capacitor
+    name: "Capacitors"
+"#,
+    );
+    std::fs::write(&manifest_path, manifest).unwrap();
+
+    let library = kicodex_core::server::load_library(tmp.path()).unwrap();
+    assert_eq!(library.part_tables.len(), 1);
+    assert_eq!(library.part_tables[0].name, "Resistors");
+    assert_eq!(library.diagnostics.len(), 1);
+    assert_eq!(library.diagnostics[0].part_table, "Capacitors");
+    assert!(library.diagnostics[0].error.contains("capacitor"));
+
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.insert("token", library);
+    let status = registry.status();
+    assert!(status[0].loaded);
+    assert_eq!(status[0].diagnostics.len(), 1);
+
+    // With no healthy table left, the library fails as a whole
+    std::fs::remove_file(tmp.path().join("data").join("resistors.csv")).unwrap();
+    assert!(kicodex_core::server::load_library(tmp.path()).is_err());
+}
+
+#[tokio::test]
+async fn test_metrics_label_unmatched_routes() {
+    let library = kicodex_core::server::load_library(&fixture_path()).unwrap();
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::new());
+    registry.insert("secret-token", library);
+    let url = serve_app(kicodex_core::server::build_router(registry.clone())).await;
+
+    let client = reqwest::Client::new();
+    for path in ["v1/nope-1", "v1/nope-2/x"] {
+        let resp = client
+            .get(format!("{url}/{path}"))
+            .header("Authorization", "Token secret-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 404);
+    }
+
+    let body = registry
+        .metrics()
+        .render(&registry.status(), &registry.token_names());
+    assert!(body.contains(
+        r#"kicodex_http_requests_total{route="unmatched",library="Sample Components Library",status="404"} 2"#
+    ));
+    assert!(!body.contains("nope"));
+}
+
+#[tokio::test]
+async fn test_metrics_count_rejected_requests() {
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::new());
+    registry.insert("secret-token", kicodex_core::server::load_library(&fixture_path()).unwrap());
+    registry.insert("other-token", kicodex_core::server::load_library(&fixture_path()).unwrap());
+    let url = serve_app(kicodex_core::server::build_router(registry.clone())).await;
+
+    let client = reqwest::Client::new();
+    let resp = client.get(format!("{url}/v1/parts/1.json")).send().await.unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = client
+        .get(format!("{url}/v1/parts/1.json"))
+        .header("Authorization", "Token wrong-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+
+    // Rejected requests have no library
+    let body = registry
+        .metrics()
+        .render(&registry.status(), &registry.token_names());
+    assert!(body.contains(
+        r#"kicodex_http_requests_total{route="/v1/parts/{partId}",library="",status="401"} 2"#
+    ));
+    assert!(!body.contains("wrong-token"));
+}
+
+#[test]
+fn test_token_names_are_unique_per_token() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    copy_fixture(tmp.path());
+
+    let entry = |token: &str, project: &str, name: &str| kicodex_core::registry::ProjectEntry {
+        token: token.to_string(),
+        project_path: Some(format!("/work/{project}")),
+        library_path: tmp.path().to_string_lossy().to_string(),
+        name: name.to_string(),
+        description: None,
+        group: None,
+        access: Default::default(),
+    };
+    let registry = kicodex_core::registry::ProjectRegistry::new();
+    registry.load_entries(&[
+        entry("t1", "amp", "Passives"),
+        entry("t2", "psu", "Passives"),
+        entry("t3", "psu", "Connectors"),
+    ]);
+
+    let names = registry.token_names();
+    assert_eq!(names["t1"], "Passives (amp)");
+    assert_eq!(names["t2"], "Passives (psu)");
+    assert_eq!(names["t3"], "Connectors");
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    let library = kicodex_core::server::load_library(&fixture_path()).unwrap();
+    let registry = Arc::new(kicodex_core::registry::ProjectRegistry::new());
+    registry.insert("secret-token", library);
+    registry.insert("other-token", kicodex_core::server::load_library(&fixture_path()).unwrap());
+    let app = kicodex_core::server::build_router(registry.clone())
+        .merge(kicodex_core::server::metrics_router(registry.clone()));
+    let url = serve_app(app).await;
+
+    let client = reqwest::Client::new();
+    for path in ["parts/category/1.json", "parts/1.json", "parts/999.json"] {
+        client
+            .get(format!("{url}/v1/{path}"))
+            .header("Authorization", "Token secret-token")
+            .send()
+            .await
+            .unwrap();
+    }
+    let resp = client.get(format!("{url}/metrics")).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let body = resp.text().await.unwrap();
+
+    // Both tokens serve a library of the same name, so they are numbered in
+    // token order: other-token is #1 and secret-token #2
+    let library = "Sample Components Library #2";
+    assert!(body.contains(&format!(
+        r#"kicodex_http_requests_total{{route="/v1/parts/{{partId}}",library="{library}",status="200"}} 1"#
+    )));
+    assert!(body.contains(&format!(
+        r#"kicodex_http_requests_total{{route="/v1/parts/{{partId}}",library="{library}",status="404"}} 1"#
+    )));
+    assert!(body.contains(&format!(
+        r#"kicodex_http_request_duration_seconds_count{{route="/v1/parts/category/{{categoryId}}",library="{library}"}} 1"#
+    )));
+    // Each token reports its own size gauges
+    for name in ["Sample Components Library #1", library] {
+        assert_eq!(body.matches(&format!(r#"kicodex_library_parts{{library="{name}"}} 3"#)).count(), 1);
+    }
+    // Three parts listed in the category plus one part detail
+    assert!(body.contains(&format!(r#"kicodex_parts_served_total{{library="{library}"}} 4"#)));
+    assert!(!body.contains("route-1"));
+    assert!(!body.contains("secret-token"));
+    assert!(!body.contains("other-token"));
+}
@@ -36,6 +36,9 @@ pub struct PartTableData {
 #[derive(Debug, Clone, Serialize)]
 pub struct TemplateInfo {
     pub based_on: Option<String>,
+    pub description: Option<String>,
+    pub name_format: Option<String>,
+    pub description_format: Option<String>,
     pub exclude_from_bom: bool,
     pub exclude_from_board: bool,
     pub exclude_from_sim: bool,
@@ -52,6 +55,10 @@ pub struct FieldInfo {
     pub visible: bool,
     pub description: Option<String>,
     pub field_type: Option<String>,
+    #[serde(default)]
+    pub expr: Option<String>,
+    #[serde(default)]
+    pub export: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -108,6 +115,12 @@ pub struct DiscoveredProject {
 pub struct RawTemplateInput {
     pub based_on: Option<String>,
     #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub name_format: Option<String>,
+    #[serde(default)]
+    pub description_format: Option<String>,
+    #[serde(default)]
     pub exclude_from_bom: bool,
     #[serde(default)]
     pub exclude_from_board: bool,
@@ -6,8 +6,8 @@ use tauri::State;
 use crate::command_types::*;
 use crate::AppState;
 
-/// Reload the in-memory registry entry for a library at the given path.
-/// Finds the matching token from the persisted registry and calls registry.reload().
+/// Reload the in-memory registry entries for a library at the given path.
+/// Finds every token serving it (projects may share a library) and calls registry.reload().
 fn reload_registry_for_path(state: &AppState, lib_path: &std::path::Path) {
     let lib_path_str = lib_path.to_string_lossy();
     let persisted = state.persisted.lock().unwrap();
@@ -25,7 +25,6 @@ fn reload_registry_for_path(state: &AppState, lib_path: &std::path::Path) {
             if let Err(e) = state.registry.reload(&entry.token, lib_path) {
                 tracing::warn!("Failed to reload library in registry: {}", e);
             }
-            return;
         }
     }
 }
@@ -41,11 +40,15 @@ pub fn register_in_kicad_lib_table(
     let mut added = 0;
 
     for name in &library_names {
-        // Look up description from persisted registry
-        let description = persisted
+        let entry = persisted
             .projects
             .iter()
-            .find(|p| p.project_path.as_deref() == Some(project_path.as_str()) && &p.name == name)
+            .find(|p| p.project_path.as_deref() == Some(project_path.as_str()) && &p.name == name);
+
+        // Aggregated libraries are registered once, under the group name
+        let name = entry.map(|e| e.httplib_name()).unwrap_or(name.as_str());
+        let description = entry
+            .filter(|e| e.group.is_none())
             .and_then(|e| e.description.clone())
             .unwrap_or_else(|| format!("KiCodex HTTP Library for {}", name));
 
@@ -234,6 +237,7 @@ pub fn get_projects(state: State<'_, AppState>) -> Result<Vec<ProjectInfo>, Stri
         let part_table_count = state
             .registry
             .get(&entry.token)
+            .filter(|lib| lib.load_error.is_none())
             .map(|lib| lib.part_tables.len())
 
             .unwrap_or(0);
@@ -364,6 +368,7 @@ pub fn apply_scan(path: String, libraries: Vec<ScanResult>) -> Result<(), String
         kicodex_core::data::project::load_project_config(&scan_dir).map_err(|e| e.to_string())?
     } else {
         kicodex_core::data::project::ProjectConfig {
+            aggregate: None,
             libraries: Vec::new(),
         }
     };
@@ -377,6 +382,7 @@ pub fn apply_scan(path: String, libraries: Vec<ScanResult>) -> Result<(), String
                 .push(kicodex_core::data::project::LibraryRef {
                     name: lib.name.clone(),
                     path: lib.path.clone(),
+                    filter: Default::default(),
                 });
         }
     }
@@ -409,6 +415,21 @@ pub fn validate_library(
 
     let mut ct_results = Vec::new();
 
+    // Part tables that failed to load are not served at all
+    for d in &library.diagnostics {
+        ct_results.push(ValidationPartTableResult {
+            name: d.part_table.clone(),
+            file: ct_files.get(&d.part_table).cloned().unwrap_or_default(),
+            errors: vec![ValidationIssue {
+                row: None,
+                id: None,
+                field: None,
+                message: d.error.clone(),
+            }],
+            warnings: Vec::new(),
+        });
+    }
+
     for ct in &library.part_tables {
         let csv_file = ct_files
             .get(&ct.name)
@@ -424,6 +445,79 @@ pub fn validate_library(
         let mut errors = Vec::new();
         let mut warnings = Vec::new();
 
+        // Check pattern ID prefixes are not shared with other part tables
+        if let Some(def) = manifest.part_tables.iter().find(|t| t.name == ct.name) {
+            let prefix = def.id_prefix();
+            let sharing: Vec<String> = manifest
+                .part_tables
+                .iter()
+                .filter(|t| t.name != ct.name && t.id_prefix() == prefix)
+                .map(|t| format!("'{}'", t.name))
+                .collect();
+            if manifest.ids.strategy == kicodex_core::data::ids::IdStrategy::Pattern
+                && !sharing.is_empty()
+            {
+                warnings.push(ValidationIssue {
+                    row: None,
+                    id: None,
+                    field: None,
+                    message: format!(
+                        "id_prefix '{}' is shared with {}; their IDs share one counter",
+                        prefix,
+                        sharing.join(", ")
+                    ),
+                });
+            }
+        }
+
+        // Check name/description formats have balanced braces and their
+        // placeholders reference known fields
+        for (format_key, format) in [
+            ("name_format", ct.template.name_format.as_deref()),
+            ("description_format", ct.template.description_format.as_deref()),
+        ] {
+            if let Some(error) = format.and_then(kicodex_core::data::schema::format_brace_error) {
+                errors.push(ValidationIssue {
+                    row: None,
+                    id: None,
+                    field: None,
+                    message: format!("{}: {}", format_key, error),
+                });
+            }
+            for placeholder in format
+                .map(kicodex_core::data::schema::format_placeholders)
+                .unwrap_or_default()
+            {
+                let message = if placeholder != "id"
+                    && !ct.template.fields.contains_key(&placeholder)
+                    && !csv_headers.contains(&placeholder)
+                {
+                    "does not match any field"
+                } else if !ct.template.is_exported(&placeholder) {
+                    "refers to an internal field and renders as empty"
+                } else {
+                    continue;
+                };
+                warnings.push(ValidationIssue {
+                    row: None,
+                    id: None,
+                    field: None,
+                    message: format!("{} placeholder '{{{}}}' {}", format_key, placeholder, message),
+                });
+            }
+        }
+
+        // Check computed fields for cycles and unknown references
+        let columns: Vec<&String> = csv_headers.iter().copied().collect();
+        for issue in kicodex_core::data::computed::check_computed_fields(&ct.template, &columns) {
+            errors.push(ValidationIssue {
+                row: None,
+                id: None,
+                field: None,
+                message: issue.to_string(),
+            });
+        }
+
         // Check required fields as columns
         for (field_name, field_def) in &ct.template.fields {
             if field_def.required && !csv_headers.contains(field_name) {
@@ -455,6 +549,27 @@ pub fn validate_library(
                 });
             }
 
+            // KiCad can only reach the first part table's part with an ID
+            if let Some((other, _)) = library.find_part(&row_id).filter(|(other, _)| other.id != ct.id) {
+                errors.push(ValidationIssue {
+                    row: Some(row_num),
+                    id: Some(row_id.clone()),
+                    field: Some("id".to_string()),
+                    message: format!("id '{}' is also used in part table '{}'", row_id, other.name),
+                });
+            }
+
+            if let Some(replacement) = kicodex_core::data::lifecycle::replacement(row) {
+                if library.find_part(replacement).is_none() {
+                    errors.push(ValidationIssue {
+                        row: Some(row_num),
+                        id: Some(row_id.clone()),
+                        field: Some(kicodex_core::data::lifecycle::REPLACED_BY_COLUMN.to_string()),
+                        message: format!("replacement id '{}' does not exist", replacement),
+                    });
+                }
+            }
+
             for (field_name, field_def) in &ct.template.fields {
                 let value = row.get(field_name).map(|s| s.as_str()).unwrap_or("");
                 let field_type = field_def.field_type.as_deref();
@@ -602,7 +717,23 @@ pub fn init_project(
         .ok_or_else(|| "Could not determine config directory".to_string())?;
 
     let mut persisted = state.persisted.lock().unwrap();
-    let port = state.port;
+    let root_url = &state.root_url;
+
+    if let Some(aggregate) = &config.aggregate {
+        // All libraries share one token and one .kicad_httplib file
+        let (token, members) = kicodex_core::discovery::auto_register::register_aggregate(
+            &project_dir,
+            aggregate,
+            &config.libraries,
+            &mut persisted,
+            root_url,
+        )
+        .map_err(|e| e.to_string())?;
+        let count = members.len();
+        state.registry.insert_aggregate(&token, &aggregate.name, members);
+        persisted.save(&registry_path).map_err(|e| e.to_string())?;
+        return Ok(format!("Initialized {} library/libraries as '{}'", count, aggregate.name));
+    }
 
     let mut count = 0;
     let project_path_str = project_dir.to_string_lossy().to_string();
@@ -613,8 +744,8 @@ pub fn init_project(
             .canonicalize()
             .unwrap_or_else(|_| library_path.clone());
 
-        let library =
-            kicodex_core::server::load_library(&library_path).map_err(|e| e.to_string())?;
+        let library = kicodex_core::server::load_library_filtered(&library_path, &lib_ref.filter)
+            .map_err(|e| e.to_string())?;
 
         // Reuse existing token if already registered, otherwise generate a new one
         let existing = persisted.projects.iter().find(|p| {
@@ -633,6 +764,8 @@ pub fn init_project(
             library_path: library_path.to_string_lossy().to_string(),
             name: lib_ref.name.clone(),
             description: description.clone(),
+            group: None,
+            access: Default::default(),
         });
 
         // Write .kicad_httplib via shared function
@@ -641,12 +774,12 @@ pub fn init_project(
             &lib_ref.name,
             description.as_deref(),
             &token,
-            port,
+            root_url,
         )
         .map_err(|e| e.to_string())?;
 
         // Insert into runtime registry
-        state.registry.insert(&token, library);
+        state.registry.insert_with_filter(&token, library, lib_ref.filter.clone());
         count += 1;
     }
 
@@ -676,6 +809,8 @@ pub fn create_library(
         description: Some(format!("KiCodex library: {}", name)),
         templates_path: "schemas".to_string(),
         part_tables: Vec::new(),
+        views: Vec::new(),
+        ids: Default::default(),
     };
     kicodex_core::data::library::save_library_manifest(&lib_dir, &manifest)
         .map_err(|e| e.to_string())?;
@@ -706,6 +841,8 @@ fn do_register_standalone(
         library_path: library_path.to_string(),
         name: name.to_string(),
         description,
+        group: None,
+        access: Default::default(),
     });
 
     persisted.save(&registry_path).map_err(|e| e.to_string())?;
@@ -829,9 +966,10 @@ pub fn add_part_table(
     lib_path: String,
     component_type_name: String,
     template: Option<RawTemplateInput>,
+    description: Option<String>,
 ) -> Result<(), String> {
     let lib_dir = PathBuf::from(&lib_path);
-    let mut manifest = kicodex_core::data::library::load_library_manifest(&lib_dir)
+    let mut manifest = kicodex_core::data::library::load_library_manifest_with_ids(&lib_dir)
         .map_err(|e| e.to_string())?;
 
     if manifest.part_tables.iter().any(|t| t.template == component_type_name) {
@@ -853,12 +991,17 @@ pub fn add_part_table(
                     visible: f.visible,
                     description: f.description.clone(),
                     field_type: f.field_type.clone(),
+                    expr: f.expr.clone(),
+                    export: f.export,
                 },
             );
         }
 
         let raw = kicodex_core::data::schema::RawTemplate {
             based_on: tmpl.based_on.clone(),
+            description: tmpl.description.clone(),
+            name_format: tmpl.name_format.clone(),
+            description_format: tmpl.description_format.clone(),
             exclude_from_bom: Some(tmpl.exclude_from_bom),
             exclude_from_board: Some(tmpl.exclude_from_board),
             exclude_from_sim: Some(tmpl.exclude_from_sim),
@@ -894,10 +1037,15 @@ pub fn add_part_table(
         .map_err(|e| e.to_string())?;
     }
 
+    let id = manifest.next_part_table_id();
     manifest.part_tables.push(kicodex_core::data::library::PartTableDef {
+        id: Some(id),
         name: component_type_name.clone(),
         file: format!("{}.csv", component_type_name),
         template: component_type_name,
+        description: description.filter(|d| !d.is_empty()),
+        notes: None,
+        id_prefix: None,
     });
 
     kicodex_core::data::library::save_library_manifest(&lib_dir, &manifest)
@@ -980,6 +1128,8 @@ pub fn get_part_table_data(
             visible: def.visible,
             description: def.description.clone(),
             field_type: def.field_type.clone(),
+            expr: def.expr.clone(),
+            export: def.export,
         })
         .collect();
 
@@ -988,6 +1138,9 @@ pub fn get_part_table_data(
         template_name: ct.template_name.clone(),
         template: TemplateInfo {
             based_on: None,
+            description: ct.template.description.clone(),
+            name_format: ct.template.name_format.clone(),
+            description_format: ct.template.description_format.clone(),
             exclude_from_bom: ct.template.exclude_from_bom,
             exclude_from_board: ct.template.exclude_from_board,
             exclude_from_sim: ct.template.exclude_from_sim,
@@ -997,12 +1150,31 @@ pub fn get_part_table_data(
     })
 }
 
+/// Drop the template's computed fields from submitted row values so they are
+/// never written back to the CSV.
+fn strip_computed_fields(
+    library_root: &std::path::Path,
+    manifest: &kicodex_core::data::library::LibraryManifest,
+    template_name: &str,
+    fields: &mut indexmap::IndexMap<String, String>,
+) {
+    let templates_dir = library_root.join(&manifest.templates_path);
+    if let Ok(template) = kicodex_core::data::schema::load_template(&templates_dir, template_name) {
+        fields.retain(|name, _| {
+            template
+                .fields
+                .get(name)
+                .is_none_or(|def| def.expr.is_none())
+        });
+    }
+}
+
 #[tauri::command]
 pub fn add_component(
     state: State<'_, AppState>,
     lib_path: String,
     component_type_name: String,
-    fields: indexmap::IndexMap<String, String>,
+    mut fields: indexmap::IndexMap<String, String>,
 ) -> Result<String, String> {
     let library_root = PathBuf::from(&lib_path);
     let manifest = kicodex_core::data::library::load_library_manifest(&library_root)
@@ -1014,6 +1186,7 @@ pub fn add_component(
         .find(|t| t.name == component_type_name || t.template == component_type_name)
         .ok_or_else(|| format!("Part table '{}' not found", component_type_name))?;
 
+    strip_computed_fields(&library_root, &manifest, &ct_def.template, &mut fields);
     let csv_path = library_root.join(&ct_def.file);
     let id = kicodex_core::data::csv_loader::append_component(&csv_path, &fields)
         .map_err(|e| e.to_string())?;
@@ -1029,7 +1202,7 @@ pub fn update_component(
     lib_path: String,
     component_type_name: String,
     id: String,
-    fields: indexmap::IndexMap<String, String>,
+    mut fields: indexmap::IndexMap<String, String>,
 ) -> Result<(), String> {
     let library_root = PathBuf::from(&lib_path);
     let manifest = kicodex_core::data::library::load_library_manifest(&library_root)
@@ -1041,6 +1214,7 @@ pub fn update_component(
         .find(|t| t.name == component_type_name || t.template == component_type_name)
         .ok_or_else(|| format!("Part table '{}' not found", component_type_name))?;
 
+    strip_computed_fields(&library_root, &manifest, &ct_def.template, &mut fields);
     let csv_path = library_root.join(&ct_def.file);
     kicodex_core::data::csv_loader::update_component(&csv_path, &id, &fields)
         .map_err(|e| e.to_string())?;
@@ -1107,11 +1281,23 @@ pub fn get_template(
             visible: def.visible,
             description: def.description.clone(),
             field_type: def.field_type.clone(),
+            expr: def.expr.clone(),
+            export: def.export,
         })
         .collect();
 
+    // Formats are inherited, so return only this template's own values to avoid
+    // baking the parent's format into it on save
+    let (raw_name_format, raw_description_format) = raw
+        .as_ref()
+        .map(|r| (r.name_format.clone(), r.description_format.clone()))
+        .unwrap_or_default();
+
     Ok(TemplateInfo {
         based_on: raw.and_then(|r| r.based_on),
+        description: resolved.description.clone(),
+        name_format: raw_name_format,
+        description_format: raw_description_format,
         exclude_from_bom: resolved.exclude_from_bom,
         exclude_from_board: resolved.exclude_from_board,
         exclude_from_sim: resolved.exclude_from_sim,
@@ -1172,12 +1358,17 @@ pub fn save_template(
                 visible: f.visible,
                 description: f.description.clone(),
                 field_type: f.field_type.clone(),
+                expr: f.expr.clone(),
+                export: f.export,
             },
         );
     }
 
     let raw = kicodex_core::data::schema::RawTemplate {
         based_on: template.based_on,
+        description: template.description,
+        name_format: template.name_format,
+        description_format: template.description_format,
         exclude_from_bom: Some(template.exclude_from_bom),
         exclude_from_board: Some(template.exclude_from_board),
         exclude_from_sim: Some(template.exclude_from_sim),
@@ -1366,7 +1557,32 @@ pub fn add_project(
         .ok_or_else(|| "Could not determine config directory".to_string())?;
 
     let mut persisted = state.persisted.lock().unwrap();
-    let port = state.port;
+    let root_url = &state.root_url;
+
+    let config = kicodex_core::data::project::load_project_config(&project_dir)
+        .map_err(|e| e.to_string())?;
+    if let Some(aggregate) = &config.aggregate {
+        // All libraries share one token and one .kicad_httplib file
+        let (token, members) = kicodex_core::discovery::auto_register::register_aggregate(
+            &project_dir,
+            aggregate,
+            &config.libraries,
+            &mut persisted,
+            root_url,
+        )
+        .map_err(|e| e.to_string())?;
+        let count = members.len();
+        state.registry.insert_aggregate(&token, &aggregate.name, members);
+        persisted.save(&registry_path).map_err(|e| e.to_string())?;
+
+        let httplib_path = project_dir
+            .join(".kicodex")
+            .join(format!("{}.kicad_httplib", aggregate.name));
+        return Ok(AddProjectResult {
+            registered_count: count,
+            httplib_paths: vec![httplib_path.to_string_lossy().to_string()],
+        });
+    }
 
     let mut count = 0;
     let mut httplib_paths = Vec::new();
@@ -1379,10 +1595,17 @@ pub fn add_project(
             .canonicalize()
             .unwrap_or_else(|_| library_path.clone());
 
+        let filter = config
+            .libraries
+            .iter()
+            .find(|lib_ref| lib_ref.name == lib.name)
+            .map(|lib_ref| lib_ref.filter.clone())
+            .unwrap_or_default();
+
         let (token, desc_str) = if lib.is_new {
             // New library: load, generate token, register
-            let library =
-                kicodex_core::server::load_library(&library_path).map_err(|e| e.to_string())?;
+            let library = kicodex_core::server::load_library_filtered(&library_path, &filter)
+                .map_err(|e| e.to_string())?;
 
             let token = uuid::Uuid::new_v4().to_string();
             let description = library.description.clone();
@@ -1398,10 +1621,12 @@ pub fn add_project(
                 library_path: library_path.to_string_lossy().to_string(),
                 name: lib.name.clone(),
                 description: description.clone(),
+                group: None,
+                access: Default::default(),
             });
 
             // Insert into runtime registry
-            state.registry.insert(&token, library);
+            state.registry.insert_with_filter(&token, library, filter);
             count += 1;
 
             (token, desc_str)
@@ -1427,7 +1652,7 @@ pub fn add_project(
             &lib.name,
             Some(&desc_str),
             &token,
-            port,
+            root_url,
         )
         .map_err(|e| e.to_string())?;
 
@@ -16,7 +16,8 @@ pub struct AppState {
     pub persisted: std::sync::Mutex<PersistedRegistry>,
     pub active_projects: std::sync::Mutex<Vec<ActiveProject>>,
     pub registry: Arc<ProjectRegistry>,
-    pub port: u16,
+    /// Root URL written into `.kicad_httplib` files.
+    pub root_url: String,
     pub kicad_libs: std::sync::Mutex<Option<KicadLibraries>>,
     pub validation_summary: std::sync::Mutex<Option<(usize, usize)>>,
 }
@@ -153,8 +154,15 @@ pub fn run() {
                 PersistedRegistry::default()
             });
 
+            let server_config =
+                kicodex_core::config::ServerConfig::load_default().unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load server config, assuming plain HTTP: {}", e);
+                    kicodex_core::config::ServerConfig::default()
+                });
+            let root_url = server_config.root_url(port);
+
             // Ensure all .kicad_httplib files are in sync before anything else
-            kicodex_core::discovery::auto_register::ensure_all_httplib_files(&persisted, port);
+            kicodex_core::discovery::auto_register::ensure_all_httplib_files(&persisted, &root_url);
 
             // Build runtime registry from persisted
             let registry = ProjectRegistry::from_persisted(&persisted).unwrap_or_else(|e| {
@@ -229,7 +237,7 @@ pub fn run() {
                 persisted: std::sync::Mutex::new(persisted.clone()),
                 active_projects: std::sync::Mutex::new(Vec::new()),
                 registry: registry.clone(),
-                port,
+                root_url: root_url.clone(),
                 kicad_libs: std::sync::Mutex::new(kicad_libs),
                 validation_summary: std::sync::Mutex::new(None),
             };
@@ -242,7 +250,7 @@ pub fn run() {
             let app_handle_discovery = app.handle().clone();
             let app_handle_active = app.handle().clone();
 
-            let engine = DiscoveryEngine::new(discovery_persisted, discovery_registry, port)
+            let engine = DiscoveryEngine::new(discovery_persisted, discovery_registry, root_url)
                 .on_discovery(move |updated_persisted| {
                     tracing::info!(
                         "Discovery: now {} project(s) registered",